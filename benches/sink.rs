@@ -0,0 +1,64 @@
+//! Measures how long `Sink01As02` takes to flush its buffer, compared to
+//! the `Vec::remove(0)` drain it used before buffering in a `VecDeque`.
+//!
+//! Run with `cargo bench --bench sink`.
+
+extern crate futures;
+extern crate futures_compat;
+extern crate futures_core;
+extern crate futures_sink;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::time::{Duration, Instant};
+
+use futures::Sink as Sink01;
+use futures_compat::futures_01::SinkInto02;
+use futures_sink::Sink as Sink02;
+
+fn flush(items: usize) -> Duration {
+    let mut sink = Vec::with_capacity(items).sink_into_02_compat();
+    for i in 0..items {
+        sink.start_send(i).unwrap();
+    }
+
+    common::with_context(&common::noop_waker(), |cx| {
+        let start = Instant::now();
+        sink.poll_flush(cx).unwrap();
+        start.elapsed()
+    })
+}
+
+/// Drains a `Vec` buffer into the v0.1 sink the way `Sink01As02` used to,
+/// taking each item from the front.
+fn flush_vec_remove(items: usize) -> Duration {
+    let mut buf = (0..items).collect::<Vec<_>>();
+    let mut sink = Vec::with_capacity(items);
+
+    let start = Instant::now();
+    while !buf.is_empty() {
+        let item = buf.remove(0);
+        Sink01::start_send(&mut sink, item).unwrap();
+    }
+    start.elapsed()
+}
+
+fn nanos(elapsed: Duration) -> u64 {
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+fn main() {
+    for &items in &[1_000, 10_000, 50_000] {
+        for &(name, bench) in &[("VecDeque", flush as fn(usize) -> Duration), ("Vec::remove(0)", flush_vec_remove)] {
+            let nanos = nanos(bench(items));
+            println!(
+                "flush {:>6} items with {:<14}: {:>12} ns ({} ns/item)",
+                items,
+                name,
+                nanos,
+                nanos / items as u64,
+            );
+        }
+    }
+}
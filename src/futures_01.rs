@@ -1,5 +1,15 @@
 //! futures 0.1.x compatibility.
+#[cfg(feature = "sink")]
+use std::cmp;
+#[cfg(feature = "sink")]
+use std::collections::VecDeque;
+#[cfg(feature = "sink")]
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "io-tokio")]
 use std::io;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use futures::{
     Async as Async01,
@@ -8,54 +18,137 @@ use futures::{
     Stream as Stream01,
     Sink as Sink01,
     StartSend as StartSend01,
-    AsyncSink as AsyncSink01,
 };
-use futures::executor::{Notify, NotifyHandle, UnsafeNotify, with_notify};
-use futures::future::{Executor as Executor01};
+#[cfg(feature = "sink")]
+use futures::{AsyncSink as AsyncSink01};
+use futures::executor::{self as executor01, Notify, NotifyHandle, Spawn, with_notify};
+use futures::future::{Executor as Executor01, ExecuteError as ExecuteError01, ExecuteErrorKind};
 
 use futures_core::{Async as Async02, Future as Future02, Never, Poll as Poll02, Stream as Stream02};
 use futures_core::executor::{Executor as Executor02, SpawnError};
 use futures_core::task::{Context, Waker};
+#[cfg(feature = "io-tokio")]
 use futures_io::{AsyncRead as AsyncRead02, AsyncWrite as AsyncWrite02};
+#[cfg(feature = "sink")]
 use futures_sink::{Sink as Sink02};
 
+#[cfg(feature = "io-tokio")]
 use tokio_io::{AsyncRead as AsyncReadTk, AsyncWrite as AsyncWriteTk};
 
-use super::futures_02::{BoxedExecutor02, Future02NeverAs01Unit};
+use super::futures_02::{
+    BoxedExecutor02,
+    Future02NeverAs01Unit,
+    LocalBoxedExecutor02,
+    LocalExecutor02,
+    LocalSharedSpawn,
+    SharedSpawn,
+};
 
 /// Wrap a `Future` from v0.1 as a `Future` from v0.2.
+///
+/// The future is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
 #[derive(Debug)]
 #[must_use = "futures do nothing unless polled"]
 pub struct Future01As02<F> {
-    v01: F,
+    v01: WakerTask<F>,
 }
 
 /// Wrap a `Stream` from v0.1 as a `Stream` from v0.2.
+///
+/// The stream is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
 #[derive(Debug)]
 #[must_use = "streams do nothing unless polled"]
 pub struct Stream01As02<S> {
-    v01: S,
+    v01: WakerTask<S>,
+}
+
+/// A `Future01As02` that maps its error with a function.
+#[must_use = "futures do nothing unless polled"]
+pub struct Future01As02MapErr<F, M> {
+    v01: WakerTask<F>,
+    map: Option<M>,
+}
+
+/// A `Stream01As02` that maps its errors with a function.
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream01As02MapErr<S, M> {
+    v01: WakerTask<S>,
+    map: M,
 }
 
 /// Wrap a `Sink` from v0.1 as a `Sink` from v0.2.
 ///
 /// Internally, this buffers all `SinkItem` values until flushed or closed.
+/// The buffer is unbounded, unless created with
+/// [`sink_into_02_compat_bounded`](SinkInto02::sink_into_02_compat_bounded).
+///
+/// The sink is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
+#[cfg(feature = "sink")]
 #[derive(Debug)]
 pub struct Sink01As02<S> where S: Sink01 {
-    v01: S,
-    buf: Vec<S::SinkItem>,
+    v01: WakerTask<S>,
+    buf: VecDeque<S::SinkItem>,
+    cap: Option<usize>,
+    closed: bool,
+}
+
+/// An error from a [`Sink01As02`](Sink01As02).
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub enum Sink01As02Error<E> {
+    /// The v0.1 sink returned an error.
+    Sink(E),
+    /// The sink was used after `poll_close` was called.
+    Closed,
 }
 
 /// Wrap an `Executor` from v0.1 as a `Executor` from v0.2.
-#[derive(Clone, Debug)]
+///
+/// The executor is shared, by the wrapper's clones and by every future
+/// spawned onto it.
+#[derive(Debug)]
 pub struct Executor01As02<E> {
+    v01: Arc<E>,
+}
+
+/// Adapt an `Executor` of boxed v0.1 futures to be wrapped by an
+/// [`Executor01As02`](Executor01As02).
+///
+/// A future rejected by the executor can't be unboxed, so its
+/// `try_spawn` only returns the v0.1 error kind.
+#[derive(Clone, Debug)]
+pub struct Executor01Boxed<E> {
     v01: E,
 }
 
+/// Wrap a single-threaded `Executor` from v0.1 as an `Executor` from v0.2.
+///
+/// Unlike [`Executor01As02`](Executor01As02), this also spawns futures that
+/// aren't `Send`, through [`LocalExecutor02`](LocalExecutor02), and so can
+/// only be used on one thread.
+#[derive(Debug)]
+pub struct LocalExecutor01As02<E> {
+    v01: Rc<E>,
+}
+
+/// A v0.1 `Future` driven in its own v0.1 task, so it can be spawned onto
+/// a v0.2 executor.
+#[derive(Debug)]
+pub(crate) struct Task01As02<F> {
+    v01: WakerTask<F>,
+}
+
 /// Wrap a IO from tokio-io as an `AsyncRead`/`AsyncWrite` from v0.2.
+///
+/// The IO is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
+#[cfg(feature = "io-tokio")]
 #[derive(Debug)]
 pub struct TokioAsAsyncIo02<I> {
-    v01: I,
+    v01: WakerTask<I>,
 }
 
 /// A trait to convert any `Future` from v0.1 into a [`Future01As02`](Future01As02).
@@ -64,44 +157,121 @@ pub struct TokioAsAsyncIo02<I> {
 pub trait FutureInto02: Future01 {
     /// Converts this future into a `Future01As02`.
     fn into_02_compat(self) -> Future01As02<Self> where Self: Sized;
+
+    /// Converts this future into a `Future01As02MapErr`, mapping its error
+    /// with `map`.
+    fn into_02_compat_map_err<M, E>(self, map: M) -> Future01As02MapErr<Self, M>
+    where
+        Self: Sized,
+        M: FnOnce(Self::Error) -> E;
 }
+
 /// A trait to convert any `Stream` from v0.1 into a [`Stream01As02`](Stream01As02).
 ///
 /// Implemented for all types that implement v0.1's `Stream` automatically.
 pub trait StreamInto02: Stream01 {
     /// Converts this stream into a `Stream01As02`.
     fn into_02_compat(self) -> Stream01As02<Self> where Self: Sized;
+
+    /// Converts this stream into a `Stream01As02MapErr`, mapping its errors
+    /// with `map`.
+    fn into_02_compat_map_err<M, E>(self, map: M) -> Stream01As02MapErr<Self, M>
+    where
+        Self: Sized,
+        M: FnMut(Self::Error) -> E;
 }
 
 /// A trait convert any `Sink` from v0.1 into a [`Sink01As02`](Sink01As02).
 ///
 /// Implemented for all types that implement v0.1's `Sink` automatically.
+#[cfg(feature = "sink")]
 pub trait SinkInto02: Sink01 {
     /// Converts this sink into a `Sink01As02`.
     fn sink_into_02_compat(self) -> Sink01As02<Self> where Self: Sized;
+
+    /// Converts this sink into a `Sink01As02` that buffers at most
+    /// `capacity` items.
+    ///
+    /// Once the buffer is full, `poll_ready` tries to flush it, and is only
+    /// ready again when there is room for another item. A `capacity` of
+    /// zero is treated as one.
+    fn sink_into_02_compat_bounded(self, capacity: usize) -> Sink01As02<Self> where Self: Sized;
 }
 
 /// A trait to convert an `Executor` from v0.1 into an [`Executor01As02`](Executor01As02).
 ///
 /// Implemented for generic v0.1 `Executor`s automatically.
+///
+/// The executor is shared, so it must be `Sync`. Executors that are only
+/// `Send` can be put behind a lock, or converted with
+/// [`into_02_compat_local`](LocalExecutorInto02::into_02_compat_local).
 pub trait ExecutorInto02: Executor01<
         Future02NeverAs01Unit<
             BoxedExecutor02,
-            Box<Future02<Item=(), Error=Never> + Send>
+            Box<dyn Future02<Item=(), Error=Never> + Send>
         >
-    > + Clone + Send + 'static {
+    > + Send + Sync + 'static {
     /// Converts this stream into a `Executor01As02`.
     fn into_02_compat(self) -> Executor01As02<Self> where Self: Sized;
 }
 
+/// A trait to convert an `Executor` of boxed v0.1 futures into an
+/// [`Executor01As02`](Executor01As02).
+///
+/// Implemented for all v0.1 `Executor`s of `Box<Future<Item=(), Error=()> + Send>`
+/// automatically, such as the ones from tokio and futures-cpupool.
+///
+/// Like with [`ExecutorInto02`](ExecutorInto02), the executor must be `Sync`.
+pub trait BoxedExecutorInto02: Executor01<Box<dyn Future01<Item=(), Error=()> + Send>> {
+    /// Converts this executor into a `Executor01As02`, boxing each future
+    /// spawned onto it.
+    ///
+    /// Since a rejected boxed future can't be unboxed, `try_spawn` only
+    /// returns the v0.1 error kind. Executors that accept the
+    /// `Executor01As02` futures directly, through
+    /// [`into_02_compat`](ExecutorInto02::into_02_compat), also give the
+    /// future back.
+    fn into_02_compat_boxed(self) -> Executor01As02<Executor01Boxed<Self>> where Self: Sized;
+}
+
+/// A trait to convert a single-threaded `Executor` from v0.1 into a
+/// [`LocalExecutor01As02`](LocalExecutor01As02).
+///
+/// Implemented for generic v0.1 `Executor`s automatically, such as the one
+/// from tokio-current-thread.
+pub trait LocalExecutorInto02: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    > + 'static {
+    /// Converts this executor into a `LocalExecutor01As02`.
+    fn into_02_compat_local(self) -> LocalExecutor01As02<Self> where Self: Sized;
+}
+
 /// A trait to convert any `AsyncRead`/`AsyncWrite` from tokio-io into a [`TokioAsAsyncIo02`](TokioAsAsyncIo02).
 ///
 /// Implemented for all types that implement tokio-io's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_v02_compat` or
+/// `write_into_v02_compat`.
+#[cfg(feature = "io-tokio")]
 pub trait TokioIntoAsyncIo02 {
     /// Converts this IO into an `TokioAsAsyncIo02`.
     fn into_v02_compat(self) -> TokioAsAsyncIo02<Self>
     where
         Self: AsyncReadTk + AsyncWriteTk + Sized;
+
+    /// Converts this read-only IO into an `TokioAsAsyncIo02`, which only
+    /// implements `AsyncRead`.
+    fn read_into_v02_compat(self) -> TokioAsAsyncIo02<Self>
+    where
+        Self: AsyncReadTk + Sized;
+
+    /// Converts this write-only IO into an `TokioAsAsyncIo02`, which only
+    /// implements `AsyncWrite`.
+    fn write_into_v02_compat(self) -> TokioAsAsyncIo02<Self>
+    where
+        Self: AsyncWriteTk + Sized;
 }
 
 impl<F> FutureInto02 for F
@@ -113,7 +283,18 @@ where
         Self: Sized,
     {
         Future01As02 {
-            v01: self,
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn into_02_compat_map_err<M, E>(self, map: M) -> Future01As02MapErr<Self, M>
+    where
+        Self: Sized,
+        M: FnOnce(Self::Error) -> E,
+    {
+        Future01As02MapErr {
+            v01: WakerTask::new(self),
+            map: Some(map),
         }
     }
 }
@@ -126,7 +307,55 @@ where
     type Error = F::Error;
 
     fn poll(&mut self, cx: &mut Context) -> Poll02<Self::Item, Self::Error> {
-        with_context_poll(cx, || self.v01.poll())
+        self.v01.with_poll(cx, |f| f.poll())
+    }
+}
+
+impl<F, M, E> Future02 for Future01As02MapErr<F, M>
+where
+    F: Future01,
+    M: FnOnce(F::Error) -> E,
+{
+    type Item = F::Item;
+    type Error = E;
+
+    fn poll(&mut self, cx: &mut Context) -> Poll02<Self::Item, Self::Error> {
+        match self.v01.with(cx, |f| f.poll()) {
+            Ok(Async01::Ready(val)) => Ok(Async02::Ready(val)),
+            Ok(Async01::NotReady) => Ok(Async02::Pending),
+            Err(err) => {
+                let map = self.map.take().expect("cannot poll Future01As02MapErr twice");
+                Err(map(err))
+            },
+        }
+    }
+}
+
+impl<F> Task01As02<F> {
+    pub(crate) fn new(f: F) -> Task01As02<F> {
+        Task01As02 {
+            v01: WakerTask::new(f),
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> F {
+        self.v01.into_inner()
+    }
+}
+
+impl<F> Future02 for Task01As02<F>
+where
+    F: Future01<Item=(), Error=()>,
+{
+    type Item = ();
+    type Error = Never;
+
+    fn poll(&mut self, cx: &mut Context) -> Poll02<Self::Item, Self::Error> {
+        // Like a v0.1 executor would, an error just ends the task.
+        match self.v01.with(cx, |f| f.poll()) {
+            Ok(Async01::Ready(())) | Err(()) => Ok(Async02::Ready(())),
+            Ok(Async01::NotReady) => Ok(Async02::Pending),
+        }
     }
 }
 
@@ -139,7 +368,18 @@ where
         Self: Sized,
     {
         Stream01As02 {
-            v01: self,
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn into_02_compat_map_err<M, E>(self, map: M) -> Stream01As02MapErr<Self, M>
+    where
+        Self: Sized,
+        M: FnMut(Self::Error) -> E,
+    {
+        Stream01As02MapErr {
+            v01: WakerTask::new(self),
+            map,
         }
     }
 }
@@ -152,7 +392,24 @@ where
     type Error = S::Error;
 
     fn poll_next(&mut self, cx: &mut Context) -> Poll02<Option<Self::Item>, Self::Error> {
-        with_context_poll(cx, || self.v01.poll())
+        self.v01.with_poll(cx, |s| s.poll())
+    }
+}
+
+impl<S, M, E> Stream02 for Stream01As02MapErr<S, M>
+where
+    S: Stream01,
+    M: FnMut(S::Error) -> E,
+{
+    type Item = S::Item;
+    type Error = E;
+
+    fn poll_next(&mut self, cx: &mut Context) -> Poll02<Option<Self::Item>, Self::Error> {
+        match self.v01.with(cx, |s| s.poll()) {
+            Ok(Async01::Ready(val)) => Ok(Async02::Ready(val)),
+            Ok(Async01::NotReady) => Ok(Async02::Pending),
+            Err(err) => Err((self.map)(err)),
+        }
     }
 }
 
@@ -164,14 +421,15 @@ where
     type SinkError = S::SinkError;
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend01<Self::SinkItem, Self::SinkError> {
-        self.v01.start_send(item)
+        self.v01.get_mut().start_send(item)
     }
 
     fn poll_complete(&mut self) -> Poll01<(), Self::SinkError> {
-        self.v01.poll_complete()
+        self.v01.get_mut().poll_complete()
     }
 }
 
+#[cfg(feature = "sink")]
 impl<S> Sink02 for Stream01As02<S>
 where
     S: Sink02,
@@ -180,22 +438,23 @@ where
     type SinkError = S::SinkError;
 
     fn poll_ready(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        self.v01.poll_ready(cx)
+        self.v01.get_mut().poll_ready(cx)
     }
 
     fn start_send(&mut self, item: Self::SinkItem) -> Result<(), Self::SinkError> {
-        self.v01.start_send(item)
+        self.v01.get_mut().start_send(item)
     }
 
     fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        self.v01.poll_flush(cx)
+        self.v01.get_mut().poll_flush(cx)
     }
 
     fn poll_close(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        self.v01.poll_close(cx)
+        self.v01.get_mut().poll_close(cx)
     }
 }
 
+#[cfg(feature = "sink")]
 impl<S> SinkInto02 for S
 where
     S: Sink01,
@@ -205,46 +464,64 @@ where
         Self: Sized,
     {
         Sink01As02 {
-            v01: self,
-            buf: Vec::new(),
+            v01: WakerTask::new(self),
+            buf: VecDeque::new(),
+            cap: None,
+            closed: false,
+        }
+    }
+
+    fn sink_into_02_compat_bounded(self, capacity: usize) -> Sink01As02<Self>
+    where
+        Self: Sized,
+    {
+        Sink01As02 {
+            v01: WakerTask::new(self),
+            buf: VecDeque::new(),
+            cap: Some(cmp::max(capacity, 1)),
+            closed: false,
         }
     }
 }
 
-impl<S> Sink02 for Sink01As02<S>
+#[cfg(feature = "sink")]
+impl<S> Sink01As02<S>
 where
     S: Sink01,
 {
-    type SinkItem = S::SinkItem;
-    type SinkError = S::SinkError;
-
-    fn poll_ready(&mut self, _cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        // Due to the internal buffer, this sink will always be ready.
-        Ok(Async02::Ready(()))
+    /// Returns the number of items waiting in the buffer to be sent to the
+    /// v0.1 sink.
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len()
     }
 
-    fn start_send(&mut self, item: Self::SinkItem) -> Result<(), Self::SinkError> {
-        // Again, the buffer is always ready.
-        self.buf.push(item);
-        Ok(())
+    /// Returns the maximum number of items the buffer holds, or `None` if
+    /// it's unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.cap
     }
 
-    fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        // Try sending all buffered items one by one.
-        loop {
-            if self.buf.len() == 0 {
-                break;
-            }
-
-            let item = self.buf.remove(0);
+    /// Takes all items that haven't been sent to the v0.1 sink yet, leaving
+    /// the buffer empty.
+    ///
+    /// This is useful after an error, to retry those items somewhere else.
+    /// The item that the v0.1 sink failed on isn't included, though some
+    /// sinks give it back in their error.
+    pub fn take_buffered(&mut self) -> Vec<S::SinkItem> {
+        self.buf.drain(..).collect()
+    }
 
-            let start_send = with_context(cx, || self.v01.start_send(item));
+    /// Try sending all buffered items one by one, returning whether the
+    /// buffer is now empty.
+    fn send_buffered(&mut self, cx: &mut Context) -> Result<bool, S::SinkError> {
+        while let Some(item) = self.buf.pop_front() {
+            let start_send = self.v01.with(cx, |s| s.start_send(item));
 
             match start_send {
                 Ok(AsyncSink01::NotReady(t)) => {
                     // Queue the item back and stop trying.
-                    self.buf.insert(0, t);
-                    break;
+                    self.buf.push_front(t);
+                    return Ok(false);
                 },
 
                 Err(e) => return Err(e),
@@ -254,14 +531,82 @@ where
             }
         }
 
-        with_context_poll(cx, || self.v01.poll_complete())
+        Ok(true)
+    }
+
+    fn is_full(&self) -> bool {
+        match self.cap {
+            Some(cap) => self.buf.len() >= cap,
+            None => false,
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S> Sink02 for Sink01As02<S>
+where
+    S: Sink01,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = Sink01As02Error<S::SinkError>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
+        if self.closed {
+            return Err(Sink01As02Error::Closed);
+        }
+
+        // Due to the internal buffer, this sink is ready until it's full.
+        if !self.is_full() {
+            return Ok(Async02::Ready(()));
+        }
+
+        // Try making some room. If the v0.1 sink can't take any more items,
+        // it has arranged to wake this task once it can.
+        self.poll_flush(cx)?;
+
+        if self.is_full() {
+            Ok(Async02::Pending)
+        } else {
+            Ok(Async02::Ready(()))
+        }
+    }
+
+    fn start_send(&mut self, item: Self::SinkItem) -> Result<(), Self::SinkError> {
+        if self.closed {
+            return Err(Sink01As02Error::Closed);
+        }
+
+        // `poll_ready` has made sure there is room in the buffer.
+        self.buf.push_back(item);
+        Ok(())
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
+        let sent = self.send_buffered(cx).map_err(Sink01As02Error::Sink)?;
+
+        // Let the v0.1 sink make progress even if some items are still
+        // buffered, but it's only flushed once they are all sent.
+        match self.v01.with_poll(cx, |s| s.poll_complete()) {
+            Ok(Async02::Ready(())) if sent => Ok(Async02::Ready(())),
+            Ok(_) => Ok(Async02::Pending),
+            Err(e) => Err(Sink01As02Error::Sink(e)),
+        }
     }
 
     fn poll_close(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
-        self.poll_flush(cx)
+        // No more items can be sent once closing has started.
+        self.closed = true;
+
+        match self.poll_flush(cx)? {
+            Async02::Ready(()) => (),
+            Async02::Pending => return Ok(Async02::Pending),
+        }
+
+        self.v01.with_poll(cx, |s| s.close()).map_err(Sink01As02Error::Sink)
     }
 }
 
+#[cfg(feature = "sink")]
 impl<S> Stream01 for Sink01As02<S>
 where
     S: Sink01 + Stream01,
@@ -270,10 +615,11 @@ where
     type Error = S::Error;
 
     fn poll(&mut self) -> Poll01<Option<Self::Item>, Self::Error> {
-        self.v01.poll()
+        self.v01.get_mut().poll()
     }
 }
 
+#[cfg(feature = "sink")]
 impl<S> Stream02 for Sink01As02<S>
 where
     S: Sink01 + Stream02,
@@ -282,7 +628,7 @@ where
     type Error = S::Error;
 
     fn poll_next(&mut self, cx: &mut Context) -> Poll02<Option<Self::Item>, Self::Error> {
-        self.v01.poll_next(cx)
+        self.v01.get_mut().poll_next(cx)
     }
 }
 
@@ -291,14 +637,28 @@ where
     E: Executor01<
         Future02NeverAs01Unit<
             BoxedExecutor02,
-            Box<Future02<Item=(), Error=Never> + Send>
+            Box<dyn Future02<Item=(), Error=Never> + Send>
         >
     >,
-    E: Clone + Send + 'static,
+    E: Send + Sync + 'static,
 {
     fn into_02_compat(self) -> Executor01As02<Self> {
         Executor01As02 {
-            v01: self,
+            v01: Arc::new(self),
+        }
+    }
+}
+
+impl<E> BoxedExecutorInto02 for E
+where
+    E: Executor01<Box<dyn Future01<Item=(), Error=()> + Send>>,
+    E: Send + Sync + 'static,
+{
+    fn into_02_compat_boxed(self) -> Executor01As02<Executor01Boxed<Self>> {
+        Executor01As02 {
+            v01: Arc::new(Executor01Boxed {
+                v01: self,
+            }),
         }
     }
 }
@@ -308,52 +668,298 @@ where
     E: Executor01<
         Future02NeverAs01Unit<
             BoxedExecutor02,
-            Box<Future02<Item=(), Error=Never> + Send>
+            Box<dyn Future02<Item=(), Error=Never> + Send>
         >
     >,
-    E: Clone + Send + 'static,
+    E: Send + Sync + 'static,
 {
-    fn spawn(&mut self, f: Box<Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.try_spawn(f).map_err(|_| SpawnError::shutdown())
+    }
+}
+
+impl<E> Executor01As02<E>
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            BoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never> + Send>
+        >
+    >,
+    E: Send + Sync + 'static,
+{
+    /// Spawns a v0.2 future onto the v0.1 executor.
+    ///
+    /// Unlike `Executor::spawn`, a rejected future is given back along with
+    /// the v0.1 error kind, so a full executor can be told apart from one
+    /// that has shut down.
+    pub fn try_spawn(
+        &self,
+        f: Box<dyn Future02<Item=(), Error=Never> + Send>,
+    ) -> Result<(), ExecuteError01<Box<dyn Future02<Item=(), Error=Never> + Send>>> {
         use super::futures_02::FutureInto01;
 
-        self.v01.execute(f.into_01_compat_never_unit(BoxedExecutor02(Box::new(self.clone()))))
-            .map_err(|_| SpawnError::shutdown())
+        let exec = BoxedExecutor02(self.v01.clone());
+        self.v01.execute(f.into_01_compat_never_unit(exec))
+            .map_err(|err| ExecuteError01::new(err.kind(), err.into_future().into_inner()))
     }
 }
 
+impl<E> Executor02 for Executor01As02<Executor01Boxed<E>>
+where
+    E: Executor01<Box<dyn Future01<Item=(), Error=()> + Send>>,
+    E: Send + Sync + 'static,
+{
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.try_spawn(f).map_err(|_| SpawnError::shutdown())
+    }
+}
 
+impl<E> Executor01As02<Executor01Boxed<E>>
+where
+    E: Executor01<Box<dyn Future01<Item=(), Error=()> + Send>>,
+    E: Send + Sync + 'static,
+{
+    /// Spawns a v0.2 future onto the v0.1 executor of boxed futures.
+    ///
+    /// The future can't be unboxed if it's rejected, so only the v0.1
+    /// error kind is returned.
+    pub fn try_spawn(&self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), ExecuteErrorKind> {
+        self.v01.spawn(BoxedExecutor02(self.v01.clone()), f)
+    }
+}
+
+impl<E> Clone for Executor01As02<E> {
+    fn clone(&self) -> Executor01As02<E> {
+        Executor01As02 {
+            v01: self.v01.clone(),
+        }
+    }
+}
+
+impl<E> SharedSpawn for E
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            BoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never> + Send>
+        >
+    >,
+{
+    fn spawn(
+        &self,
+        exec: BoxedExecutor02,
+        f: Box<dyn Future02<Item=(), Error=Never> + Send>,
+    ) -> Result<(), ExecuteErrorKind> {
+        use super::futures_02::FutureInto01;
+
+        self.execute(f.into_01_compat_never_unit(exec)).map_err(|err| err.kind())
+    }
+}
+
+impl<E> SharedSpawn for Executor01Boxed<E>
+where
+    E: Executor01<Box<dyn Future01<Item=(), Error=()> + Send>>,
+{
+    fn spawn(
+        &self,
+        exec: BoxedExecutor02,
+        f: Box<dyn Future02<Item=(), Error=Never> + Send>,
+    ) -> Result<(), ExecuteErrorKind> {
+        use super::futures_02::FutureInto01;
+
+        self.v01.execute(Box::new(f.into_01_compat_never_unit(exec))).map_err(|err| err.kind())
+    }
+}
+
+impl<E> LocalExecutorInto02 for E
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    >,
+    E: 'static,
+{
+    fn into_02_compat_local(self) -> LocalExecutor01As02<Self> {
+        LocalExecutor01As02 {
+            v01: Rc::new(self),
+        }
+    }
+}
+
+impl<E> Executor02 for LocalExecutor01As02<E>
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    >,
+    E: 'static,
+{
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.spawn_local(f)
+    }
+}
+
+impl<E> LocalExecutor02 for LocalExecutor01As02<E>
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    >,
+    E: 'static,
+{
+    fn spawn_local(&mut self, f: Box<dyn Future02<Item=(), Error=Never>>) -> Result<(), SpawnError> {
+        self.try_spawn(f).map_err(|_| SpawnError::shutdown())
+    }
+}
+
+impl<E> LocalExecutor01As02<E>
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    >,
+    E: 'static,
+{
+    /// Spawns a v0.2 future onto the v0.1 executor.
+    ///
+    /// Like [`Executor01As02::try_spawn`](Executor01As02::try_spawn), a
+    /// rejected future is given back along with the v0.1 error kind.
+    pub fn try_spawn(
+        &self,
+        f: Box<dyn Future02<Item=(), Error=Never>>,
+    ) -> Result<(), ExecuteError01<Box<dyn Future02<Item=(), Error=Never>>>> {
+        self.v01.spawn_local(LocalBoxedExecutor02(self.v01.clone()), f)
+    }
+}
+
+impl<E> Clone for LocalExecutor01As02<E> {
+    fn clone(&self) -> LocalExecutor01As02<E> {
+        LocalExecutor01As02 {
+            v01: self.v01.clone(),
+        }
+    }
+}
+
+impl<E> LocalSharedSpawn for E
+where
+    E: Executor01<
+        Future02NeverAs01Unit<
+            LocalBoxedExecutor02,
+            Box<dyn Future02<Item=(), Error=Never>>
+        >
+    >,
+{
+    fn spawn_local(
+        &self,
+        exec: LocalBoxedExecutor02,
+        f: Box<dyn Future02<Item=(), Error=Never>>,
+    ) -> Result<(), ExecuteError01<Box<dyn Future02<Item=(), Error=Never>>>> {
+        use super::futures_02::FutureInto01;
+
+        self.execute(f.into_01_compat_never_unit(exec))
+            .map_err(|err| ExecuteError01::new(err.kind(), err.into_future().into_inner()))
+    }
+}
+
+
+#[cfg(feature = "io-tokio")]
 impl<I> TokioIntoAsyncIo02 for I {
     fn into_v02_compat(self) -> TokioAsAsyncIo02<Self>
     where
         Self: AsyncReadTk + AsyncWriteTk + Sized,
     {
         TokioAsAsyncIo02 {
-            v01: self,
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn read_into_v02_compat(self) -> TokioAsAsyncIo02<Self>
+    where
+        Self: AsyncReadTk + Sized,
+    {
+        TokioAsAsyncIo02 {
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn write_into_v02_compat(self) -> TokioAsAsyncIo02<Self>
+    where
+        Self: AsyncWriteTk + Sized,
+    {
+        TokioAsAsyncIo02 {
+            v01: WakerTask::new(self),
         }
     }
 }
 
+#[cfg(feature = "io-tokio")]
 impl<I: AsyncReadTk> AsyncRead02 for TokioAsAsyncIo02<I> {
     fn poll_read(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll02<usize, io::Error> {
-        with_context_poll(cx, || self.v01.poll_read(buf))
+        self.v01.with_poll(cx, |io| io.poll_read(buf))
     }
 }
 
+#[cfg(feature = "io-tokio")]
 impl<I: AsyncWriteTk> AsyncWrite02 for TokioAsAsyncIo02<I> {
     fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll02<usize, io::Error> {
-        with_context_poll(cx, || self.v01.poll_write(buf))
+        self.v01.with_poll(cx, |io| io.poll_write(buf))
     }
 
     fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), io::Error> {
-        with_context_poll(cx, || self.v01.poll_flush())
+        self.v01.with_poll(cx, |io| io.poll_flush())
     }
 
     fn poll_close(&mut self, cx: &mut Context) -> Poll02<(), io::Error> {
-        with_context_poll(cx, || self.v01.shutdown())
+        self.v01.with_poll(cx, |io| io.shutdown())
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: io::Read> io::Read for TokioAsAsyncIo02<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.v01.get_mut().read(buf)
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: io::Write> io::Write for TokioAsAsyncIo02<I> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.v01.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.v01.get_mut().flush()
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncReadTk> AsyncReadTk for TokioAsAsyncIo02<I> {
+    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
+        self.v01.get_ref().prepare_uninitialized_buffer(buf)
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncWriteTk> AsyncWriteTk for TokioAsAsyncIo02<I> {
+    fn shutdown(&mut self) -> Poll01<(), io::Error> {
+        self.v01.get_mut().shutdown()
     }
 }
 
 /// Execute a function with the context used as a v0.1 `Notifier`.
+///
+/// Each `task::current()` call made by `f` allocates a new notify handle.
+/// The wrappers in this module avoid that by reusing one for as long as
+/// they're woken by the same task.
 pub fn with_context<F, R>(cx: &mut Context, f: F) -> R
 where
     F: FnOnce() -> R,
@@ -367,16 +973,146 @@ pub fn with_context_poll<F, R, E>(cx: &mut Context, f: F) -> Poll02<R, E>
 where
     F: FnOnce() -> Poll01<R, E>,
 {
-    with_context(cx, move || {
-        match f() {
-            Ok(Async01::Ready(val)) => Ok(Async02::Ready(val)),
-            Ok(Async01::NotReady) => Ok(Async02::Pending),
-            Err(err) => Err(err),
+    with_context(cx, move || into_poll02(f()))
+}
+
+impl<F: fmt::Debug, M> fmt::Debug for Future01As02MapErr<F, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Future01As02MapErr")
+            .field("v01", &self.v01)
+            .finish()
+    }
+}
+
+impl<S: fmt::Debug, M> fmt::Debug for Stream01As02MapErr<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stream01As02MapErr")
+            .field("v01", &self.v01)
+            .finish()
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<E: fmt::Display> fmt::Display for Sink01As02Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Sink01As02Error::Sink(ref e) => e.fmt(f),
+            Sink01As02Error::Closed => f.write_str("sink is closed"),
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<E: Error> Error for Sink01As02Error<E> {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            Sink01As02Error::Sink(ref e) => Some(e),
+            Sink01As02Error::Closed => None,
+        }
+    }
+}
+
+fn into_poll02<R, E>(poll: Poll01<R, E>) -> Poll02<R, E> {
+    match poll {
+        Ok(Async01::Ready(val)) => Ok(Async02::Ready(val)),
+        Ok(Async01::NotReady) => Ok(Async02::Pending),
+        Err(err) => Err(err),
+    }
+}
+
+/// A v0.1 task, notified by the waker of a v0.2 `Context`.
+#[derive(Debug)]
+pub(crate) struct WakerTask<T, W = Waker> {
+    spawn: Spawn<T>,
+    notify: Option<Arc<NotifyWaker<W>>>,
+}
+
+impl<T, W> WakerTask<T, W>
+where
+    W: AnyWaker,
+{
+    pub(crate) fn new(t: T) -> WakerTask<T, W> {
+        WakerTask {
+            spawn: executor01::spawn(t),
+            notify: None,
+        }
+    }
+
+    #[cfg(feature = "io-tokio")]
+    pub(crate) fn get_ref(&self) -> &T {
+        self.spawn.get_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.spawn.get_mut()
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.spawn.into_inner()
+    }
+
+    /// Execute a function inside this task, with the waker used as its
+    /// `Notifier`.
+    pub(crate) fn with_waker<F, R>(&mut self, waker: &W, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        // Every `NotifyHandle` given to the task shares the same `Arc`, which
+        // is only replaced when polled with a waker for some other task.
+        let stale = match self.notify {
+            Some(ref notify) => !notify.0.will_wake(waker),
+            None => true,
+        };
+
+        if stale {
+            self.notify = Some(Arc::new(NotifyWaker(waker.clone())));
+        }
+
+        match self.notify {
+            Some(ref notify) => self.spawn.poll_fn_notify(notify, 0, f),
+            None => unreachable!("notify was just set"),
         }
-    })
+    }
 }
 
-struct NotifyWaker(Waker);
+impl<T> WakerTask<T> {
+    /// Execute a function inside this task, with the context used as its
+    /// `Notifier`.
+    fn with<F, R>(&mut self, cx: &mut Context, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.with_waker(cx.waker(), f)
+    }
+
+    /// Like `with`, converting v0.1 `Poll` into v0.2 version.
+    fn with_poll<F, R, E>(&mut self, cx: &mut Context, f: F) -> Poll02<R, E>
+    where
+        F: FnOnce(&mut T) -> Poll01<R, E>,
+    {
+        self.with(cx, move |t| into_poll02(f(t)))
+    }
+}
+
+/// The waker types that can be used to notify a v0.1 task.
+pub(crate) trait AnyWaker: Clone + Send + Sync + 'static {
+    fn wake(&self);
+
+    fn will_wake(&self, other: &Self) -> bool;
+}
+
+impl AnyWaker for Waker {
+    fn wake(&self) {
+        Waker::wake(self);
+    }
+
+    fn will_wake(&self, other: &Waker) -> bool {
+        Waker::will_wake(self, other)
+    }
+}
+
+#[derive(Debug)]
+struct NotifyWaker<W>(W);
 
 #[allow(missing_debug_implementations)]
 #[derive(Clone)]
@@ -385,27 +1121,12 @@ struct WakerToHandle<'a>(&'a Waker);
 #[doc(hidden)]
 impl<'a> From<WakerToHandle<'a>> for NotifyHandle {
     fn from(handle: WakerToHandle<'a>) -> NotifyHandle {
-        let ptr = Box::new(NotifyWaker(handle.0.clone()));
-
-        unsafe {
-            NotifyHandle::new(Box::into_raw(ptr))
-        }
+        NotifyHandle::from(Arc::new(NotifyWaker(handle.0.clone())))
     }
 }
 
-impl Notify for NotifyWaker {
+impl<W: AnyWaker> Notify for NotifyWaker<W> {
     fn notify(&self, _: usize) {
         self.0.wake();
     }
 }
-
-unsafe impl UnsafeNotify for NotifyWaker {
-    unsafe fn clone_raw(&self) -> NotifyHandle {
-        WakerToHandle(&self.0).into()
-    }
-
-    unsafe fn drop_raw(&self) {
-        let ptr: *const UnsafeNotify = self;
-        drop(Box::from_raw(ptr as *mut UnsafeNotify));
-    }
-}
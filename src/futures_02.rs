@@ -1,21 +1,45 @@
 //! futures 0.2.x compatibility.
+#[cfg(feature = "io-tokio")]
 use std::io;
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-use futures::{Async as Async01, Future as Future01, Poll as Poll01, Stream as Stream01};
+use futures::{
+    Async as Async01,
+    Future as Future01,
+    Poll as Poll01,
+    Sink as Sink01,
+    StartSend as StartSend01,
+    Stream as Stream01,
+};
+#[cfg(feature = "sink")]
+use futures::{AsyncSink as AsyncSink01};
+use futures::future::{Executor as Executor01, ExecuteError as ExecuteError01, ExecuteErrorKind};
 use futures::task::{self as task01, Task as Task01};
 
-use futures_core::{Async as Async02, Future as Future02, Never, Stream as Stream02};
+use futures_core::{Async as Async02, Future as Future02, Never, Poll as Poll02, Stream as Stream02};
 use futures_core::task::{Context, LocalMap, Wake, Waker};
 use futures_core::executor::{Executor as Executor02, SpawnError};
-use futures_io::{AsyncRead as AsyncRead02, AsyncWrite as AsyncWrite02};
+#[cfg(feature = "io-tokio")]
+use futures_io::{AsyncRead as AsyncRead02, AsyncWrite as AsyncWrite02, Initializer};
+#[cfg(feature = "sink")]
+use futures_sink::{Sink as Sink02};
+#[cfg(feature = "io-tokio")]
 use tokio_io::{AsyncRead as AsyncReadTk, AsyncWrite as AsyncWriteTk};
 
+use super::futures_01::Task01As02;
+
 /// Wrap a `Future` from v0.2 as a `Future` from v0.1.
 #[derive(Debug)]
 #[must_use = "futures do nothing unless polled"]
 pub struct Future02As01<E, F> {
     exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
     v02: F,
 }
 
@@ -24,7 +48,19 @@ pub struct Future02As01<E, F> {
 #[must_use = "futures do nothing unless polled"]
 pub struct Future02NeverAs01Unit<E, F> {
     exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
+    v02: F,
+}
+
+/// A `Future02As01` that maps its error with a function.
+#[must_use = "futures do nothing unless polled"]
+pub struct Future02As01MapErr<E, F, M> {
+    exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
     v02: F,
+    map: Option<M>,
 }
 
 /// Wrap a `Stream` from v0.2 as a `Stream` from v0.1.
@@ -32,23 +68,269 @@ pub struct Future02NeverAs01Unit<E, F> {
 #[must_use = "streams do nothing unless polled"]
 pub struct Stream02As01<E, S> {
     exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
+    v02: S,
+}
+
+/// A `Stream02As01` that maps its errors with a function.
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream02As01MapErr<E, S, M> {
+    exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
+    v02: S,
+    map: M,
+}
+
+/// A `Stream02As01` that maps errors of `Never` to `()`.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream02NeverAs01Unit<E, S> {
+    exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
+    v02: S,
+}
+
+/// Wrap a `Sink` from v0.2 as a `Sink` from v0.1.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct Sink02As01<E, S> {
+    exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
+    v02: S,
+}
+
+/// A `Sink02As01` that maps errors of `Never` to `()`.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct Sink02NeverAs01Unit<E, S> {
+    exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
     v02: S,
 }
 
 /// Wrap some `AsyncRead` or `AsyncWrite` from v0.2 as the same from tokio-io.
+#[cfg(feature = "io-tokio")]
 #[derive(Debug)]
 pub struct AsyncIo02AsTokio<E, S> {
     exec: E,
+    locals: LocalMap,
+    waker: CurrentWaker,
     v02: S,
 }
 
-/// A wrapper of `Box<Executor>` because it's missing from the futures crate (lolz).
+/// Wrap an `Executor` from v0.2 as an `Executor` from v0.1.
+///
+/// Spawning onto it from within its own v0.2 executor's `spawn`, such as
+/// from a future that executor polls right away, fails with `Shutdown`
+/// instead of deadlocking.
+///
+/// A future the v0.2 executor rejects is given back in the `ExecuteError`,
+/// so the executor must not poll or keep a future it returns an error for.
+/// If it does, either the spawn or the next poll of that future panics.
+#[derive(Debug)]
+pub struct Executor02As01<E> {
+    v02: Mutex<E>,
+}
+
+/// A shared handle to the executor of an `Executor01As02`, given to each
+/// future spawned onto it.
+///
+/// Cloning it only bumps a reference count.
 #[allow(missing_debug_implementations)]
-pub struct BoxedExecutor02(pub(crate) Box<Executor02 + Send>);
+#[derive(Clone)]
+pub struct BoxedExecutor02(pub(crate) Arc<dyn SharedSpawn + Send + Sync>);
 
 impl Executor02 for BoxedExecutor02 {
-    fn spawn(&mut self, f: Box<Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
-        (&mut *self.0).spawn(f)
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.0.spawn(self.clone(), f).map_err(|_| SpawnError::shutdown())
+    }
+}
+
+/// An executor that can spawn through a shared reference, giving each
+/// spawned future a handle to itself.
+pub(crate) trait SharedSpawn {
+    fn spawn(
+        &self,
+        exec: BoxedExecutor02,
+        f: Box<dyn Future02<Item=(), Error=Never> + Send>,
+    ) -> Result<(), ExecuteErrorKind>;
+}
+
+/// Wrap a [`LocalExecutor02`](LocalExecutor02) as an `Executor` from v0.1.
+///
+/// Unlike [`Executor02As01`](Executor02As01), this spawns futures that
+/// aren't `Send`, and so can only be used on one thread. Like it, rejected
+/// futures are given back, so the executor must not poll or keep them.
+#[derive(Debug)]
+pub struct LocalExecutor02As01<E> {
+    v02: RefCell<E>,
+}
+
+/// A v0.2 executor of futures that aren't `Send`.
+///
+/// futures-core 0.2 only has a trait for executors of `Send` futures, so
+/// single-threaded executors, like the spawner of a `LocalPool`, implement
+/// this one to be used with [`LocalExecutor02As01`](LocalExecutor02As01).
+///
+/// Executors from other crates, such as futures-executor's `LocalExecutor`,
+/// can't implement it directly, but a newtype around them can:
+///
+/// ```rust,ignore
+/// use futures_compat::futures_02::{LocalExecutor02, LocalExecutorInto01};
+///
+/// struct Spawner(futures_executor::LocalExecutor);
+///
+/// impl LocalExecutor02 for Spawner {
+///     fn spawn_local(&mut self, f: Box<dyn Future<Item=(), Error=Never>>) -> Result<(), SpawnError> {
+///         self.0.spawn_local(f)
+///     }
+/// }
+///
+/// let pool = futures_executor::LocalPool::new();
+/// let exec01 = Spawner(pool.executor()).into_01_compat_local();
+/// ```
+pub trait LocalExecutor02 {
+    /// Spawns a future to run on the current thread.
+    ///
+    /// If an error is returned, the future must not have been polled, and
+    /// must not be kept to be polled later.
+    fn spawn_local(&mut self, f: Box<dyn Future02<Item=(), Error=Never>>) -> Result<(), SpawnError>;
+}
+
+/// A shared handle to the executor of a `LocalExecutor01As02`, given to
+/// each future spawned onto it.
+///
+/// Like the executor, it can only be used on one thread. Cloning it only
+/// bumps a reference count.
+#[allow(missing_debug_implementations)]
+#[derive(Clone)]
+pub struct LocalBoxedExecutor02(pub(crate) Rc<dyn LocalSharedSpawn>);
+
+impl Executor02 for LocalBoxedExecutor02 {
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.spawn_local(f)
+    }
+}
+
+impl LocalExecutor02 for LocalBoxedExecutor02 {
+    fn spawn_local(&mut self, f: Box<dyn Future02<Item=(), Error=Never>>) -> Result<(), SpawnError> {
+        self.0.spawn_local(self.clone(), f).map_err(|_| SpawnError::shutdown())
+    }
+}
+
+/// The single-threaded counterpart of `SharedSpawn`.
+pub(crate) trait LocalSharedSpawn {
+    fn spawn_local(
+        &self,
+        exec: LocalBoxedExecutor02,
+        f: Box<dyn Future02<Item=(), Error=Never>>,
+    ) -> Result<(), ExecuteError01<Box<dyn Future02<Item=(), Error=Never>>>>;
+}
+
+/// An `Executor` from v0.2 that spawns onto the current thread's default
+/// executor, as set by [`set_default_executor`](set_default_executor).
+///
+/// Spawning fails with `SpawnError::shutdown()` if no default executor is
+/// set on the thread doing the spawning. Note that `Context::spawn` panics
+/// on that error, while `Context::executor().spawn` returns it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultExecutor02;
+
+/// A guard that restores the previous default executor when dropped.
+///
+/// Returned by [`set_default_executor`](set_default_executor).
+#[derive(Debug)]
+#[must_use = "the default executor is unset when the guard is dropped"]
+pub struct DefaultExecutorGuard {
+    depth: usize,
+    // The guard must be dropped on the thread it was created on.
+    _not_send: PhantomData<Rc<()>>,
+}
+
+thread_local! {
+    // The default executors set on this thread, the last one being current.
+    static DEFAULT_EXECUTORS: RefCell<Vec<Box<dyn Executor02>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sets the default executor of the current thread, used by
+/// [`DefaultExecutor02`](DefaultExecutor02), until the returned guard is
+/// dropped.
+///
+/// Guards can be nested, and then each restores the executor that was the
+/// default when it was created.
+///
+/// # Panics
+///
+/// Dropping a guard panics if a guard created after it is still alive.
+pub fn set_default_executor<E>(exec: E) -> DefaultExecutorGuard
+where
+    E: Executor02 + 'static,
+{
+    let depth = DEFAULT_EXECUTORS.with(|cell| {
+        let mut execs = cell.borrow_mut();
+        execs.push(Box::new(exec));
+        execs.len()
+    });
+    DefaultExecutorGuard {
+        depth,
+        _not_send: PhantomData,
+    }
+}
+
+impl Drop for DefaultExecutorGuard {
+    fn drop(&mut self) {
+        // The thread-local is gone if the guard is dropped during thread
+        // teardown, and then there's nothing to restore.
+        let _ = DEFAULT_EXECUTORS.try_with(|cell| {
+            let mut execs = cell.borrow_mut();
+            if execs.len() == self.depth {
+                execs.pop();
+            } else if execs.len() > self.depth {
+                if thread::panicking() {
+                    // Panicking again would abort, so unset the newer
+                    // guards' executors along with this one.
+                    execs.truncate(self.depth - 1);
+                } else {
+                    // Restoring this guard's executor would leave the newer
+                    // guard's executor set after that guard is gone.
+                    panic!("default executor guards dropped out of order");
+                }
+            }
+            // Otherwise an older guard already unset this one's executor,
+            // having been dropped out of order while panicking.
+        });
+    }
+}
+
+fn with_default_executor<F>(f: F) -> Result<(), SpawnError>
+where
+    F: FnOnce(&mut dyn Executor02) -> Result<(), SpawnError>,
+{
+    DEFAULT_EXECUTORS.try_with(|cell| {
+        // The default executor is already borrowed if it spawns onto the
+        // default executor itself.
+        match cell.try_borrow_mut() {
+            Ok(mut execs) => match execs.last_mut() {
+                Some(exec) => f(&mut **exec),
+                None => Err(SpawnError::shutdown()),
+            },
+            Err(_) => Err(SpawnError::shutdown()),
+        }
+    }).unwrap_or_else(|_| Err(SpawnError::shutdown()))
+}
+
+impl Executor02 for DefaultExecutor02 {
+    fn spawn(&mut self, f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        with_default_executor(|exec| exec.spawn(f))
+    }
+
+    fn status(&self) -> Result<(), SpawnError> {
+        with_default_executor(|exec| exec.status())
     }
 }
 
@@ -73,6 +355,26 @@ pub trait FutureInto01: Future02 {
     where
         Self: Future02<Error=Never> + Sized,
         E: Executor02;
+
+    /// Converts this future into a `Future02As01MapErr`, mapping its error
+    /// with `map`.
+    ///
+    /// An executor is required to allow this wrapped future to still access
+    /// `Context::spawn` while wrapped.
+    fn into_01_compat_map_err<E, M, U>(self, exec: E, map: M) -> Future02As01MapErr<E, Self, M>
+    where
+        Self: Sized,
+        E: Executor02,
+        M: FnOnce(Self::Error) -> U;
+
+    /// Converts this future into a `Future02As01` that spawns onto the
+    /// thread's default executor.
+    ///
+    /// Spawning through the `Context` while wrapped fails unless a default
+    /// executor was set with [`set_default_executor`](set_default_executor).
+    fn into_01_compat_default(self) -> Future02As01<DefaultExecutor02, Self>
+    where
+        Self: Sized;
 }
 
 /// A trait to convert any `Stream` from v0.2 into a [`Stream02As01`](Stream02As01).
@@ -87,11 +389,84 @@ pub trait StreamInto01: Stream02 {
     where
         Self: Sized,
         E: Executor02;
+
+    /// Converts this stream into a `Stream02NeverAs01Unit`.
+    ///
+    /// An executor is required to allow this wrapped stream to still access
+    /// `Context::spawn` while wrapped.
+    fn into_01_compat_never_unit<E>(self, exec: E) -> Stream02NeverAs01Unit<E, Self>
+    where
+        Self: Stream02<Error=Never> + Sized,
+        E: Executor02;
+
+    /// Converts this stream into a `Stream02As01MapErr`, mapping its errors
+    /// with `map`.
+    ///
+    /// An executor is required to allow this wrapped stream to still access
+    /// `Context::spawn` while wrapped.
+    fn into_01_compat_map_err<E, M, U>(self, exec: E, map: M) -> Stream02As01MapErr<E, Self, M>
+    where
+        Self: Sized,
+        E: Executor02,
+        M: FnMut(Self::Error) -> U;
+
+    /// Converts this stream into a `Stream02As01` that spawns onto the
+    /// thread's default executor.
+    ///
+    /// Spawning through the `Context` while wrapped fails unless a default
+    /// executor was set with [`set_default_executor`](set_default_executor).
+    fn into_01_compat_default(self) -> Stream02As01<DefaultExecutor02, Self>
+    where
+        Self: Sized;
+}
+
+/// A trait to convert any `Sink` from v0.2 into a [`Sink02As01`](Sink02As01).
+///
+/// Implemented for all types that implement v0.2's `Sink` automatically.
+#[cfg(feature = "sink")]
+pub trait SinkInto01: Sink02 {
+    /// Converts this sink into a `Sink02As01`.
+    ///
+    /// An executor is required to allow this wrapped sink to still access
+    /// `Context::spawn` while wrapped.
+    fn sink_into_01_compat<E>(self, exec: E) -> Sink02As01<E, Self>
+    where
+        Self: Sized,
+        E: Executor02;
+
+    /// Converts this sink into a `Sink02NeverAs01Unit`.
+    ///
+    /// An executor is required to allow this wrapped sink to still access
+    /// `Context::spawn` while wrapped.
+    fn sink_into_01_compat_never_unit<E>(self, exec: E) -> Sink02NeverAs01Unit<E, Self>
+    where
+        Self: Sink02<SinkError=Never> + Sized,
+        E: Executor02;
+}
+
+/// A trait to convert an `Executor` from v0.2 into an [`Executor02As01`](Executor02As01).
+///
+/// Implemented for all v0.2 `Executor`s automatically.
+pub trait ExecutorInto01: Executor02 {
+    /// Converts this executor into a `Executor02As01`.
+    fn into_01_compat(self) -> Executor02As01<Self> where Self: Sized;
+}
+
+/// A trait to convert a [`LocalExecutor02`](LocalExecutor02) into a
+/// [`LocalExecutor02As01`](LocalExecutor02As01).
+///
+/// Implemented for all `LocalExecutor02`s automatically.
+pub trait LocalExecutorInto01: LocalExecutor02 {
+    /// Converts this executor into a `LocalExecutor02As01`.
+    fn into_01_compat_local(self) -> LocalExecutor02As01<Self> where Self: Sized;
 }
 
 /// A trait to convert any `AsyncRead`/`AsyncWrite` from v0.2 into a [`AsyncIo02AsTokio`](AsyncIo02AsTokio).
 ///
 /// Implemented for all types that implement v0.2's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_tokio_compat` or
+/// `write_into_tokio_compat`.
+#[cfg(feature = "io-tokio")]
 pub trait AsyncIoIntoTokio {
     /// Converts this IO into an `AsyncIo02AsTokio`.
     ///
@@ -101,6 +476,35 @@ pub trait AsyncIoIntoTokio {
     where
         Self: AsyncRead02 + AsyncWrite02 + Sized,
         E: Executor02;
+
+    /// Converts this IO into an `AsyncIo02AsTokio` that spawns onto the
+    /// thread's default executor.
+    ///
+    /// Spawning through the `Context` while wrapped fails unless a default
+    /// executor was set with [`set_default_executor`](set_default_executor).
+    fn into_tokio_compat_default(self) -> AsyncIo02AsTokio<DefaultExecutor02, Self>
+    where
+        Self: AsyncRead02 + AsyncWrite02 + Sized;
+
+    /// Converts this read-only IO into an `AsyncIo02AsTokio`, which only
+    /// implements `AsyncRead`.
+    ///
+    /// An executor is required to allow this wrapped future to still access
+    /// `Context::spawn` while wrapped.
+    fn read_into_tokio_compat<E>(self, exec: E) -> AsyncIo02AsTokio<E, Self>
+    where
+        Self: AsyncRead02 + Sized,
+        E: Executor02;
+
+    /// Converts this write-only IO into an `AsyncIo02AsTokio`, which only
+    /// implements `AsyncWrite`.
+    ///
+    /// An executor is required to allow this wrapped future to still access
+    /// `Context::spawn` while wrapped.
+    fn write_into_tokio_compat<E>(self, exec: E) -> AsyncIo02AsTokio<E, Self>
+    where
+        Self: AsyncWrite02 + Sized,
+        E: Executor02;
 }
 
 impl<F> FutureInto01 for F
@@ -114,6 +518,8 @@ where
     {
         Future02As01 {
             exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
             v02: self,
         }
     }
@@ -125,9 +531,33 @@ where
     {
         Future02NeverAs01Unit {
             exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn into_01_compat_map_err<E, M, U>(self, exec: E, map: M) -> Future02As01MapErr<E, Self, M>
+    where
+        Self: Sized,
+        E: Executor02,
+        M: FnOnce(Self::Error) -> U,
+    {
+        Future02As01MapErr {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
             v02: self,
+            map: Some(map),
         }
     }
+
+    fn into_01_compat_default(self) -> Future02As01<DefaultExecutor02, Self>
+    where
+        Self: Sized,
+    {
+        self.into_01_compat(DefaultExecutor02)
+    }
 }
 
 impl<E, F> Future01 for Future02As01<E, F>
@@ -139,9 +569,7 @@ where
     type Error = F::Error;
 
     fn poll(&mut self) -> Poll01<Self::Item, Self::Error> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll(&mut cx) {
             Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
@@ -151,6 +579,35 @@ where
     }
 }
 
+impl<E, F, M, U> Future01 for Future02As01MapErr<E, F, M>
+where
+    F: Future02,
+    E: Executor02,
+    M: FnOnce(F::Error) -> U,
+{
+    type Item = F::Item;
+    type Error = U;
+
+    fn poll(&mut self) -> Poll01<Self::Item, Self::Error> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(err) => {
+                let map = self.map.take().expect("cannot poll Future02As01MapErr twice");
+                Err(map(err))
+            },
+        }
+    }
+}
+
+impl<E, F> Future02NeverAs01Unit<E, F> {
+    pub(crate) fn into_inner(self) -> F {
+        self.v02
+    }
+}
+
 impl<E, F> Future01 for Future02NeverAs01Unit<E, F>
 where
     F: Future02<Error=Never>,
@@ -160,9 +617,7 @@ where
     type Error = ();
 
     fn poll(&mut self) -> Poll01<Self::Item, Self::Error> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll(&mut cx) {
             Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
@@ -183,9 +638,46 @@ where
     {
         Stream02As01 {
             exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn into_01_compat_never_unit<E>(self, exec: E) -> Stream02NeverAs01Unit<E, Self>
+    where
+        Self: Sized,
+        E: Executor02,
+    {
+        Stream02NeverAs01Unit {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn into_01_compat_map_err<E, M, U>(self, exec: E, map: M) -> Stream02As01MapErr<E, Self, M>
+    where
+        Self: Sized,
+        E: Executor02,
+        M: FnMut(Self::Error) -> U,
+    {
+        Stream02As01MapErr {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
             v02: self,
+            map,
         }
     }
+
+    fn into_01_compat_default(self) -> Stream02As01<DefaultExecutor02, Self>
+    where
+        Self: Sized,
+    {
+        self.into_01_compat(DefaultExecutor02)
+    }
 }
 
 impl<E, S> Stream01 for Stream02As01<E, S>
@@ -197,9 +689,7 @@ where
     type Error = S::Error;
 
     fn poll(&mut self) -> Poll01<Option<Self::Item>, Self::Error> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll_next(&mut cx) {
             Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
@@ -209,6 +699,379 @@ where
     }
 }
 
+impl<E, S, M, U> Stream01 for Stream02As01MapErr<E, S, M>
+where
+    S: Stream02,
+    E: Executor02,
+    M: FnMut(S::Error) -> U,
+{
+    type Item = S::Item;
+    type Error = U;
+
+    fn poll(&mut self) -> Poll01<Option<Self::Item>, Self::Error> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_next(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(err) => Err((self.map)(err)),
+        }
+    }
+}
+
+impl<E, S> Stream01 for Stream02NeverAs01Unit<E, S>
+where
+    S: Stream02<Error=Never>,
+    E: Executor02,
+{
+    type Item = S::Item;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll01<Option<Self::Item>, Self::Error> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_next(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(never) => match never {}
+        }
+    }
+}
+
+impl<E, S> Sink01 for Stream02As01<E, S>
+where
+    S: Sink01,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend01<Self::SinkItem, Self::SinkError> {
+        self.v02.start_send(item)
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), Self::SinkError> {
+        self.v02.poll_complete()
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<E, S> Sink02 for Stream02As01<E, S>
+where
+    S: Sink02,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
+        self.v02.poll_ready(cx)
+    }
+
+    fn start_send(&mut self, item: Self::SinkItem) -> Result<(), Self::SinkError> {
+        self.v02.start_send(item)
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
+        self.v02.poll_flush(cx)
+    }
+
+    fn poll_close(&mut self, cx: &mut Context) -> Poll02<(), Self::SinkError> {
+        self.v02.poll_close(cx)
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S> SinkInto01 for S
+where
+    S: Sink02,
+{
+    fn sink_into_01_compat<E>(self, exec: E) -> Sink02As01<E, Self>
+    where
+        Self: Sized,
+        E: Executor02,
+    {
+        Sink02As01 {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn sink_into_01_compat_never_unit<E>(self, exec: E) -> Sink02NeverAs01Unit<E, Self>
+    where
+        Self: Sized,
+        E: Executor02,
+    {
+        Sink02NeverAs01Unit {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<E, S> Sink01 for Sink02As01<E, S>
+where
+    S: Sink02,
+    E: Executor02,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend01<Self::SinkItem, Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_ready(&mut cx) {
+            Ok(Async02::Ready(())) => {
+                self.v02.start_send(item)?;
+                Ok(AsyncSink01::Ready)
+            },
+            Ok(Async02::Pending) => Ok(AsyncSink01::NotReady(item)),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_flush(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn close(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_close(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<E, S> Sink01 for Sink02NeverAs01Unit<E, S>
+where
+    S: Sink02<SinkError=Never>,
+    E: Executor02,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = ();
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend01<Self::SinkItem, Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_ready(&mut cx) {
+            Ok(Async02::Ready(())) => match self.v02.start_send(item) {
+                Ok(()) => Ok(AsyncSink01::Ready),
+                Err(never) => match never {}
+            },
+            Ok(Async02::Pending) => Ok(AsyncSink01::NotReady(item)),
+            Err(never) => match never {}
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_flush(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(never) => match never {}
+        }
+    }
+
+    fn close(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
+
+        match self.v02.poll_close(&mut cx) {
+            Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
+            Ok(Async02::Pending) => Ok(Async01::NotReady),
+            Err(never) => match never {}
+        }
+    }
+}
+
+impl<E> ExecutorInto01 for E
+where
+    E: Executor02,
+{
+    fn into_01_compat(self) -> Executor02As01<Self> {
+        Executor02As01 {
+            v02: Mutex::new(self),
+        }
+    }
+}
+
+impl<E, F> Executor01<F> for Executor02As01<E>
+where
+    E: Executor02,
+    F: Future01<Item=(), Error=()> + Send + 'static,
+{
+    fn execute(&self, f: F) -> Result<(), ExecuteError01<F>> {
+        // The executor stays locked while it spawns, so this thread can't
+        // spawn onto it again until then.
+        let _spawning = match Spawning::enter(self as *const Self as usize) {
+            Some(spawning) => spawning,
+            None => return Err(ExecuteError01::new(ExecuteErrorKind::Shutdown, f)),
+        };
+
+        let mut exec = match self.v02.lock() {
+            Ok(exec) => exec,
+            Err(_) => return Err(ExecuteError01::new(ExecuteErrorKind::Shutdown, f)),
+        };
+
+        // A v0.2 executor drops the future if it can't be spawned, so keep a
+        // way to get it back.
+        let (task, slot) = Reclaim::new(Task01As02::new(f));
+
+        exec.spawn(Box::new(task)).map_err(|_| {
+            ExecuteError01::new(ExecuteErrorKind::Shutdown, slot.reclaim().into_inner())
+        })
+    }
+}
+
+thread_local! {
+    // The addresses of the `Executor02As01`s this thread is spawning onto.
+    static SPAWNING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks an `Executor02As01` as spawning on this thread until dropped.
+struct Spawning(usize);
+
+impl Spawning {
+    /// Returns `None` if this thread is already spawning onto `id`.
+    fn enter(id: usize) -> Option<Spawning> {
+        SPAWNING.with(|spawning| {
+            let mut spawning = spawning.borrow_mut();
+            if spawning.contains(&id) {
+                None
+            } else {
+                spawning.push(id);
+                Some(Spawning(id))
+            }
+        })
+    }
+}
+
+impl Drop for Spawning {
+    fn drop(&mut self) {
+        let _ = SPAWNING.try_with(|spawning| {
+            let mut spawning = spawning.borrow_mut();
+            if let Some(pos) = spawning.iter().rposition(|&id| id == self.0) {
+                spawning.remove(pos);
+            }
+        });
+    }
+}
+
+impl<E> LocalExecutorInto01 for E
+where
+    E: LocalExecutor02,
+{
+    fn into_01_compat_local(self) -> LocalExecutor02As01<Self> {
+        LocalExecutor02As01 {
+            v02: RefCell::new(self),
+        }
+    }
+}
+
+impl<E, F> Executor01<F> for LocalExecutor02As01<E>
+where
+    E: LocalExecutor02,
+    F: Future01<Item=(), Error=()> + 'static,
+{
+    fn execute(&self, f: F) -> Result<(), ExecuteError01<F>> {
+        // A spawn made from within the executor's own `spawn_local` finds
+        // it already borrowed.
+        let mut exec = match self.v02.try_borrow_mut() {
+            Ok(exec) => exec,
+            Err(_) => return Err(ExecuteError01::new(ExecuteErrorKind::Shutdown, f)),
+        };
+
+        let (task, slot) = Reclaim::new(Task01As02::new(f));
+
+        exec.spawn_local(Box::new(task)).map_err(|_| {
+            ExecuteError01::new(ExecuteErrorKind::Shutdown, slot.reclaim().into_inner())
+        })
+    }
+}
+
+/// A future that can be taken back by its spawner until it's first polled.
+///
+/// This relies on the executor dropping a future it rejects without polling
+/// it. If it polls the future first, `reclaim` panics, and if it keeps the
+/// future and polls it after it was reclaimed, that poll panics.
+struct Reclaim<F> {
+    slot: Arc<Mutex<Option<F>>>,
+    fut: Option<F>,
+}
+
+/// The spawner's half of a `Reclaim`.
+struct ReclaimSlot<F>(Arc<Mutex<Option<F>>>);
+
+impl<F> Reclaim<F> {
+    fn new(f: F) -> (Reclaim<F>, ReclaimSlot<F>) {
+        let slot = Arc::new(Mutex::new(Some(f)));
+        let reclaim = Reclaim {
+            slot: slot.clone(),
+            fut: None,
+        };
+        (reclaim, ReclaimSlot(slot))
+    }
+
+    fn get_mut(&mut self) -> &mut F {
+        if self.fut.is_none() {
+            self.fut = take(&self.slot);
+        }
+        self.fut.as_mut().expect("future was reclaimed by its spawner")
+    }
+}
+
+impl<F> ReclaimSlot<F> {
+    /// Take back a future that was rejected by an executor.
+    fn reclaim(self) -> F {
+        take(&self.0).expect("rejected future was polled")
+    }
+}
+
+fn take<F>(slot: &Mutex<Option<F>>) -> Option<F> {
+    match slot.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    }
+}
+
+impl<F> Future02 for Reclaim<F>
+where
+    F: Future02,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self, cx: &mut Context) -> Poll02<Self::Item, Self::Error> {
+        self.get_mut().poll(cx)
+    }
+}
+
+impl<F> Future01 for Reclaim<F>
+where
+    F: Future01,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll01<Self::Item, Self::Error> {
+        self.get_mut().poll()
+    }
+}
+
+#[cfg(feature = "io-tokio")]
 impl<I> AsyncIoIntoTokio for I {
     fn into_tokio_compat<E>(self, exec: E) -> AsyncIo02AsTokio<E, Self>
     where
@@ -217,16 +1080,50 @@ impl<I> AsyncIoIntoTokio for I {
     {
         AsyncIo02AsTokio {
             exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn into_tokio_compat_default(self) -> AsyncIo02AsTokio<DefaultExecutor02, Self>
+    where
+        Self: AsyncRead02 + AsyncWrite02 + Sized,
+    {
+        self.into_tokio_compat(DefaultExecutor02)
+    }
+
+    fn read_into_tokio_compat<E>(self, exec: E) -> AsyncIo02AsTokio<E, Self>
+    where
+        Self: AsyncRead02 + Sized,
+        E: Executor02,
+    {
+        AsyncIo02AsTokio {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
+            v02: self,
+        }
+    }
+
+    fn write_into_tokio_compat<E>(self, exec: E) -> AsyncIo02AsTokio<E, Self>
+    where
+        Self: AsyncWrite02 + Sized,
+        E: Executor02,
+    {
+        AsyncIo02AsTokio {
+            exec,
+            locals: LocalMap::new(),
+            waker: CurrentWaker::default(),
             v02: self,
         }
     }
 }
 
+#[cfg(feature = "io-tokio")]
 impl<E: Executor02, I: AsyncRead02> io::Read for AsyncIo02AsTokio<E, I> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll_read(&mut cx, buf) {
             Ok(Async02::Ready(val)) => Ok(val),
@@ -236,11 +1133,10 @@ impl<E: Executor02, I: AsyncRead02> io::Read for AsyncIo02AsTokio<E, I> {
     }
 }
 
+#[cfg(feature = "io-tokio")]
 impl<E: Executor02, I: AsyncWrite02> io::Write for AsyncIo02AsTokio<E, I> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll_write(&mut cx, buf) {
             Ok(Async02::Ready(val)) => Ok(val),
@@ -250,9 +1146,7 @@ impl<E: Executor02, I: AsyncWrite02> io::Write for AsyncIo02AsTokio<E, I> {
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll_flush(&mut cx) {
             Ok(Async02::Ready(val)) => Ok(val),
@@ -262,10 +1156,12 @@ impl<E: Executor02, I: AsyncWrite02> io::Write for AsyncIo02AsTokio<E, I> {
     }
 }
 
+#[cfg(feature = "io-tokio")]
 fn would_block() -> io::Error {
     io::Error::from(io::ErrorKind::WouldBlock)
 }
 
+#[cfg(feature = "io-tokio")]
 impl<E: Executor02, I: AsyncRead02> AsyncReadTk for AsyncIo02AsTokio<E, I> {
     unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
         let init = self.v02.initializer();
@@ -278,11 +1174,10 @@ impl<E: Executor02, I: AsyncRead02> AsyncReadTk for AsyncIo02AsTokio<E, I> {
     }
 }
 
+#[cfg(feature = "io-tokio")]
 impl<E: Executor02, I: AsyncWrite02> AsyncWriteTk for AsyncIo02AsTokio<E, I> {
     fn shutdown(&mut self) -> Poll01<(), io::Error> {
-        let mut locals = LocalMap::new();
-        let waker = current_as_waker();
-        let mut cx = Context::new(&mut locals, &waker, &mut self.exec);
+        let mut cx = Context::new(&mut self.locals, self.waker.current(), &mut self.exec);
 
         match self.v02.poll_close(&mut cx) {
             Ok(Async02::Ready(val)) => Ok(Async01::Ready(val)),
@@ -292,14 +1187,96 @@ impl<E: Executor02, I: AsyncWrite02> AsyncWriteTk for AsyncIo02AsTokio<E, I> {
     }
 }
 
-// Maybe it's possible to do all this without cloning and allocating,
-// but I just wanted to get this working now. Optimzations welcome.
+#[cfg(feature = "io-tokio")]
+impl<E, I: AsyncRead02> AsyncRead02 for AsyncIo02AsTokio<E, I> {
+    unsafe fn initializer(&self) -> Initializer {
+        self.v02.initializer()
+    }
+
+    fn poll_read(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll02<usize, io::Error> {
+        self.v02.poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<E, I: AsyncWrite02> AsyncWrite02 for AsyncIo02AsTokio<E, I> {
+    fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll02<usize, io::Error> {
+        self.v02.poll_write(cx, buf)
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll02<(), io::Error> {
+        self.v02.poll_flush(cx)
+    }
+
+    fn poll_close(&mut self, cx: &mut Context) -> Poll02<(), io::Error> {
+        self.v02.poll_close(cx)
+    }
+}
+
+impl<E: fmt::Debug, F: fmt::Debug, M> fmt::Debug for Future02As01MapErr<E, F, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Future02As01MapErr")
+            .field("exec", &self.exec)
+            .field("locals", &self.locals)
+            .field("waker", &self.waker)
+            .field("v02", &self.v02)
+            .finish()
+    }
+}
+
+impl<E: fmt::Debug, S: fmt::Debug, M> fmt::Debug for Stream02As01MapErr<E, S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stream02As01MapErr")
+            .field("exec", &self.exec)
+            .field("locals", &self.locals)
+            .field("waker", &self.waker)
+            .field("v02", &self.v02)
+            .finish()
+    }
+}
+
+/// A `Waker` for the current v0.1 task.
+///
+/// Building one means allocating, so it's only rebuilt when the wrapper
+/// is polled by a different v0.1 task.
+#[derive(Debug)]
+pub(crate) struct CurrentWaker<W = Waker> {
+    cached: Option<(Arc<Current>, W)>,
+}
+
+impl<W> Default for CurrentWaker<W> {
+    fn default() -> CurrentWaker<W> {
+        CurrentWaker {
+            cached: None,
+        }
+    }
+}
+
+impl<W> CurrentWaker<W>
+where
+    W: From<Arc<Current>>,
+{
+    pub(crate) fn current(&mut self) -> &W {
+        let stale = match self.cached {
+            Some((ref current, _)) => !current.0.will_notify_current(),
+            None => true,
+        };
+
+        if stale {
+            let current = Arc::new(Current(task01::current()));
+            let waker = W::from(current.clone());
+            self.cached = Some((current, waker));
+        }
 
-fn current_as_waker() -> Waker {
-    Waker::from(Arc::new(Current(task01::current())))
+        match self.cached {
+            Some((_, ref waker)) => waker,
+            None => unreachable!("waker was just cached"),
+        }
+    }
 }
 
-struct Current(Task01);
+#[derive(Debug)]
+pub(crate) struct Current(pub(crate) Task01);
 
 impl Wake for Current {
     fn wake(arc_self: &Arc<Self>) {
@@ -0,0 +1,958 @@
+//! futures 0.3.x and `std::future` compatibility with futures 0.1.x and 0.2.x,
+//! and futures-io 0.3.x compatibility with tokio-io.
+//!
+//! Requires the `futures-03` feature.
+#[cfg(feature = "io-tokio")]
+use std::cmp;
+use std::future::Future as Future03;
+#[cfg(feature = "io-tokio")]
+use std::io;
+#[cfg(feature = "sink")]
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context as Context03, Poll as Poll03, Waker as Waker03};
+
+use futures::{
+    Async as Async01,
+    Future as Future01,
+    Poll as Poll01,
+    Stream as Stream01,
+};
+#[cfg(feature = "sink")]
+use futures::{AsyncSink as AsyncSink01, Sink as Sink01, StartSend as StartSend01};
+
+use futures_core::{Async as Async02, Future as Future02, Poll as Poll02, Stream as Stream02};
+use futures_core::executor::{Executor as Executor02};
+use futures_core::task::{Context as Context02, LocalMap, Waker as Waker02};
+
+use futures_core_03::{TryFuture as TryFuture03, TryStream as TryStream03, Stream as Stream03};
+#[cfg(feature = "io-tokio")]
+use futures_io_03::{
+    AsyncBufRead as AsyncBufRead03,
+    AsyncRead as AsyncRead03,
+    AsyncWrite as AsyncWrite03,
+};
+#[cfg(feature = "sink")]
+use futures_sink_03::{Sink as Sink03};
+
+#[cfg(feature = "io-tokio")]
+use tokio_io::{AsyncRead as AsyncReadTk, AsyncWrite as AsyncWriteTk};
+
+use super::futures_01::WakerTask;
+use super::futures_02::CurrentWaker;
+use super::std_task::WakerCache;
+
+#[cfg(feature = "io-tokio")]
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// Wrap a `Future` from v0.1 as a `std::future::Future`.
+///
+/// The future is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Future01As03<F> {
+    v01: WakerTask<F, Waker03>,
+}
+
+/// Wrap a `Stream` from v0.1 as a `Stream` from v0.3.
+///
+/// The stream is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream01As03<S> {
+    v01: WakerTask<S, Waker03>,
+}
+
+/// Wrap a `Sink` from v0.1 as a `Sink` from v0.3.
+///
+/// The sink is driven in its own v0.1 task, so v0.1 task-local data
+/// is kept across polls.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct Sink01As03<S> where S: Sink01 {
+    v01: WakerTask<S, Waker03>,
+    slot: Option<S::SinkItem>,
+}
+
+/// Wrap a `std::future::Future` as a `Future` from v0.1.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Future03As01<F> {
+    waker: CurrentWaker<Waker03>,
+    v03: F,
+}
+
+/// Wrap a `Stream` from v0.3 as a `Stream` from v0.1.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream03As01<S> {
+    waker: CurrentWaker<Waker03>,
+    v03: S,
+}
+
+/// Wrap a `Sink` from v0.3 as a `Sink` from v0.1.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct Sink03As01<S, T> {
+    waker: CurrentWaker<Waker03>,
+    v03: S,
+    _item: PhantomData<fn(T)>,
+}
+
+/// Wrap a `Future` from v0.2 as a `std::future::Future`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Future02As03<E, F> {
+    exec: E,
+    locals: LocalMap,
+    waker: WakerCache<Waker03, Waker02>,
+    v02: F,
+}
+
+/// Wrap a `Stream` from v0.2 as a `Stream` from v0.3.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream02As03<E, S> {
+    exec: E,
+    locals: LocalMap,
+    waker: WakerCache<Waker03, Waker02>,
+    v02: S,
+}
+
+/// Wrap a `std::future::Future` as a `Future` from v0.2.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Future03As02<F> {
+    waker: WakerCache<Waker02, Waker03>,
+    v03: F,
+}
+
+/// Wrap a `Stream` from v0.3 as a `Stream` from v0.2.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Stream03As02<S> {
+    waker: WakerCache<Waker02, Waker03>,
+    v03: S,
+}
+
+/// Wrap a IO from tokio-io as an `AsyncRead`/`AsyncWrite`/`AsyncBufRead`
+/// from v0.3.
+///
+/// The IO is driven in its own v0.1 task. Reads go through an internal
+/// buffer, which is skipped for reads at least as large as it when it's empty.
+#[cfg(feature = "io-tokio")]
+#[derive(Debug)]
+pub struct TokioAsAsyncIo03<I> {
+    v01: WakerTask<I, Waker03>,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+/// Wrap some `AsyncRead` or `AsyncWrite` from v0.3 as the same from tokio-io.
+///
+/// Like any tokio-io IO, it must be used from within a v0.1 task.
+#[cfg(feature = "io-tokio")]
+#[derive(Debug)]
+pub struct AsyncIo03AsTokio<I> {
+    waker: CurrentWaker<Waker03>,
+    v03: I,
+}
+
+/// A trait to convert any `Future` from v0.1 into a [`Future01As03`](Future01As03).
+///
+/// Implemented for all types that implement v0.1's `Future` automatically.
+pub trait Future01Into03: Future01 {
+    /// Converts this future into a `Future01As03`.
+    fn into_03_compat(self) -> Future01As03<Self> where Self: Sized;
+}
+
+/// A trait to convert any `Stream` from v0.1 into a [`Stream01As03`](Stream01As03).
+///
+/// Implemented for all types that implement v0.1's `Stream` automatically.
+pub trait Stream01Into03: Stream01 {
+    /// Converts this stream into a `Stream01As03`.
+    fn into_03_compat(self) -> Stream01As03<Self> where Self: Sized;
+}
+
+/// A trait to convert any `Sink` from v0.1 into a [`Sink01As03`](Sink01As03).
+///
+/// Implemented for all types that implement v0.1's `Sink` automatically.
+#[cfg(feature = "sink")]
+pub trait Sink01Into03: Sink01 {
+    /// Converts this sink into a `Sink01As03`.
+    fn sink_into_03_compat(self) -> Sink01As03<Self> where Self: Sized;
+}
+
+/// A trait to convert any `std::future::Future` resolving to a `Result` into
+/// a [`Future03As01`](Future03As01).
+///
+/// Implemented for all types that implement v0.3's `TryFuture` automatically.
+pub trait Future03Into01: TryFuture03 {
+    /// Converts this future into a `Future03As01`.
+    ///
+    /// The future must be `Unpin`, so `!Unpin` futures, such as the ones
+    /// from `async` blocks, need to be put in a `Pin<Box<_>>` first.
+    fn into_01_compat(self) -> Future03As01<Self> where Self: Sized;
+}
+
+/// A trait to convert any `Stream` of `Result`s from v0.3 into a
+/// [`Stream03As01`](Stream03As01).
+///
+/// Implemented for all types that implement v0.3's `TryStream` automatically.
+pub trait Stream03Into01: TryStream03 {
+    /// Converts this stream into a `Stream03As01`.
+    ///
+    /// The stream must be `Unpin`, so `!Unpin` streams need to be put in a
+    /// `Pin<Box<_>>` first.
+    fn into_01_compat(self) -> Stream03As01<Self> where Self: Sized;
+}
+
+/// A trait to convert any `Sink` from v0.3 into a [`Sink03As01`](Sink03As01).
+///
+/// Implemented for all types that implement v0.3's `Sink` automatically.
+#[cfg(feature = "sink")]
+pub trait Sink03Into01<T>: Sink03<T> {
+    /// Converts this sink into a `Sink03As01`.
+    ///
+    /// The sink must be `Unpin`, so `!Unpin` sinks need to be put in a
+    /// `Pin<Box<_>>` first.
+    fn sink_into_01_compat(self) -> Sink03As01<Self, T> where Self: Sized;
+}
+
+/// A trait to convert any `Future` from v0.2 into a [`Future02As03`](Future02As03).
+///
+/// Implemented for all types that implement v0.2's `Future` automatically.
+pub trait Future02Into03: Future02 {
+    /// Converts this future into a `Future02As03`.
+    ///
+    /// An executor is required to allow this wrapped future to still access
+    /// `Context::spawn` while wrapped.
+    fn into_03_compat<E>(self, exec: E) -> Future02As03<E, Self>
+    where
+        Self: Sized,
+        E: Executor02;
+}
+
+/// A trait to convert any `Stream` from v0.2 into a [`Stream02As03`](Stream02As03).
+///
+/// Implemented for all types that implement v0.2's `Stream` automatically.
+pub trait Stream02Into03: Stream02 {
+    /// Converts this stream into a `Stream02As03`.
+    ///
+    /// An executor is required to allow this wrapped stream to still access
+    /// `Context::spawn` while wrapped.
+    fn into_03_compat<E>(self, exec: E) -> Stream02As03<E, Self>
+    where
+        Self: Sized,
+        E: Executor02;
+}
+
+/// A trait to convert any `std::future::Future` resolving to a `Result` into
+/// a [`Future03As02`](Future03As02).
+///
+/// Implemented for all types that implement v0.3's `TryFuture` automatically.
+pub trait Future03Into02: TryFuture03 {
+    /// Converts this future into a `Future03As02`.
+    ///
+    /// The future must be `Unpin`, so `!Unpin` futures, such as the ones
+    /// from `async` blocks, need to be put in a `Pin<Box<_>>` first.
+    fn into_02_compat(self) -> Future03As02<Self> where Self: Sized;
+}
+
+/// A trait to convert any `Stream` of `Result`s from v0.3 into a
+/// [`Stream03As02`](Stream03As02).
+///
+/// Implemented for all types that implement v0.3's `TryStream` automatically.
+pub trait Stream03Into02: TryStream03 {
+    /// Converts this stream into a `Stream03As02`.
+    ///
+    /// The stream must be `Unpin`, so `!Unpin` streams need to be put in a
+    /// `Pin<Box<_>>` first.
+    fn into_02_compat(self) -> Stream03As02<Self> where Self: Sized;
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from tokio-io into a [`TokioAsAsyncIo03`](TokioAsAsyncIo03).
+///
+/// Implemented for all types that implement tokio-io's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_io_03_compat` or
+/// `write_into_io_03_compat`.
+#[cfg(feature = "io-tokio")]
+pub trait TokioIntoAsyncIo03 {
+    /// Converts this IO into a `TokioAsAsyncIo03`.
+    fn into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncReadTk + AsyncWriteTk + Sized;
+
+    /// Converts this read-only IO into a `TokioAsAsyncIo03`, which only
+    /// implements `AsyncRead` and `AsyncBufRead`.
+    fn read_into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncReadTk + Sized;
+
+    /// Converts this write-only IO into a `TokioAsAsyncIo03`, which only
+    /// implements `AsyncWrite`.
+    fn write_into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncWriteTk + Sized;
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from v0.3 into a [`AsyncIo03AsTokio`](AsyncIo03AsTokio).
+///
+/// Implemented for all types that implement v0.3's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `io_03_read_into_tokio_compat`
+/// or `io_03_write_into_tokio_compat`.
+///
+/// The IO must be `Unpin`, so `!Unpin` IOs need to be put in a `Pin<Box<_>>` first.
+#[cfg(feature = "io-tokio")]
+pub trait AsyncIo03IntoTokio {
+    /// Converts this IO into an `AsyncIo03AsTokio`.
+    fn io_03_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncRead03 + AsyncWrite03 + Unpin + Sized;
+
+    /// Converts this read-only IO into an `AsyncIo03AsTokio`, which only
+    /// implements `AsyncRead`, and `BufRead` if this is an `AsyncBufRead`.
+    fn io_03_read_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncRead03 + Unpin + Sized;
+
+    /// Converts this write-only IO into an `AsyncIo03AsTokio`, which only
+    /// implements `AsyncWrite`.
+    fn io_03_write_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncWrite03 + Unpin + Sized;
+}
+
+impl<F> Future01Into03 for F
+where
+    F: Future01,
+{
+    fn into_03_compat(self) -> Future01As03<Self>
+    where
+        Self: Sized,
+    {
+        Future01As03 {
+            v01: WakerTask::new(self),
+        }
+    }
+}
+
+// v0.1 futures are never pinned.
+impl<F> Unpin for Future01As03<F> {}
+
+impl<F> Future03 for Future01As03<F>
+where
+    F: Future01,
+{
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Self::Output> {
+        into_poll03(self.get_mut().v01.with_waker(cx.waker(), |f| f.poll()))
+    }
+}
+
+impl<S> Stream01Into03 for S
+where
+    S: Stream01,
+{
+    fn into_03_compat(self) -> Stream01As03<Self>
+    where
+        Self: Sized,
+    {
+        Stream01As03 {
+            v01: WakerTask::new(self),
+        }
+    }
+}
+
+impl<S> Unpin for Stream01As03<S> {}
+
+impl<S> Stream03 for Stream01As03<S>
+where
+    S: Stream01,
+{
+    type Item = Result<S::Item, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Option<Self::Item>> {
+        match self.get_mut().v01.with_waker(cx.waker(), |s| s.poll()) {
+            Ok(Async01::Ready(Some(val))) => Poll03::Ready(Some(Ok(val))),
+            Ok(Async01::Ready(None)) => Poll03::Ready(None),
+            Ok(Async01::NotReady) => Poll03::Pending,
+            Err(err) => Poll03::Ready(Some(Err(err))),
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S> Sink01Into03 for S
+where
+    S: Sink01,
+{
+    fn sink_into_03_compat(self) -> Sink01As03<Self>
+    where
+        Self: Sized,
+    {
+        Sink01As03 {
+            v01: WakerTask::new(self),
+            slot: None,
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S> Sink01As03<S>
+where
+    S: Sink01,
+{
+    /// Try sending the item given to `start_send` to the v0.1 sink.
+    fn poll_send_slot(&mut self, waker: &Waker03) -> Poll03<Result<(), S::SinkError>> {
+        let item = match self.slot.take() {
+            Some(item) => item,
+            None => return Poll03::Ready(Ok(())),
+        };
+
+        match self.v01.with_waker(waker, |s| s.start_send(item)) {
+            Ok(AsyncSink01::Ready) => Poll03::Ready(Ok(())),
+            Ok(AsyncSink01::NotReady(item)) => {
+                self.slot = Some(item);
+                Poll03::Pending
+            },
+            Err(err) => Poll03::Ready(Err(err)),
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S> Unpin for Sink01As03<S> where S: Sink01 {}
+
+#[cfg(feature = "sink")]
+impl<S> Sink03<S::SinkItem> for Sink01As03<S>
+where
+    S: Sink01,
+{
+    type Error = S::SinkError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Result<(), Self::Error>> {
+        // Only one item is held at a time, so the sink is ready once the
+        // previous one has been taken by the v0.1 sink.
+        self.get_mut().poll_send_slot(cx.waker())
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: S::SinkItem) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        debug_assert!(this.slot.is_none(), "start_send called without poll_ready");
+        this.slot = Some(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        match this.poll_send_slot(cx.waker()) {
+            Poll03::Ready(Ok(())) => (),
+            other => return other,
+        }
+
+        into_poll03(this.v01.with_waker(cx.waker(), |s| s.poll_complete()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        match this.poll_send_slot(cx.waker()) {
+            Poll03::Ready(Ok(())) => (),
+            other => return other,
+        }
+
+        into_poll03(this.v01.with_waker(cx.waker(), |s| s.close()))
+    }
+}
+
+impl<F> Future03Into01 for F
+where
+    F: TryFuture03,
+{
+    fn into_01_compat(self) -> Future03As01<Self>
+    where
+        Self: Sized,
+    {
+        Future03As01 {
+            waker: CurrentWaker::default(),
+            v03: self,
+        }
+    }
+}
+
+impl<F> Future01 for Future03As01<F>
+where
+    F: TryFuture03 + Unpin,
+{
+    type Item = F::Ok;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll01<Self::Item, Self::Error> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).try_poll(&mut cx) {
+            Poll03::Ready(Ok(val)) => Ok(Async01::Ready(val)),
+            Poll03::Ready(Err(err)) => Err(err),
+            Poll03::Pending => Ok(Async01::NotReady),
+        }
+    }
+}
+
+impl<S> Stream03Into01 for S
+where
+    S: TryStream03,
+{
+    fn into_01_compat(self) -> Stream03As01<Self>
+    where
+        Self: Sized,
+    {
+        Stream03As01 {
+            waker: CurrentWaker::default(),
+            v03: self,
+        }
+    }
+}
+
+impl<S> Stream01 for Stream03As01<S>
+where
+    S: TryStream03 + Unpin,
+{
+    type Item = S::Ok;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll01<Option<Self::Item>, Self::Error> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).try_poll_next(&mut cx) {
+            Poll03::Ready(Some(Ok(val))) => Ok(Async01::Ready(Some(val))),
+            Poll03::Ready(Some(Err(err))) => Err(err),
+            Poll03::Ready(None) => Ok(Async01::Ready(None)),
+            Poll03::Pending => Ok(Async01::NotReady),
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S, T> Sink03Into01<T> for S
+where
+    S: Sink03<T>,
+{
+    fn sink_into_01_compat(self) -> Sink03As01<Self, T>
+    where
+        Self: Sized,
+    {
+        Sink03As01 {
+            waker: CurrentWaker::default(),
+            v03: self,
+            _item: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "sink")]
+impl<S, T> Sink01 for Sink03As01<S, T>
+where
+    S: Sink03<T> + Unpin,
+{
+    type SinkItem = T;
+    type SinkError = S::Error;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend01<Self::SinkItem, Self::SinkError> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).poll_ready(&mut cx) {
+            Poll03::Ready(Ok(())) => {
+                Pin::new(&mut self.v03).start_send(item)?;
+                Ok(AsyncSink01::Ready)
+            },
+            Poll03::Ready(Err(err)) => Err(err),
+            Poll03::Pending => Ok(AsyncSink01::NotReady(item)),
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        into_poll01(Pin::new(&mut self.v03).poll_flush(&mut cx))
+    }
+
+    fn close(&mut self) -> Poll01<(), Self::SinkError> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        into_poll01(Pin::new(&mut self.v03).poll_close(&mut cx))
+    }
+}
+
+impl<F> Future02Into03 for F
+where
+    F: Future02,
+{
+    fn into_03_compat<E>(self, exec: E) -> Future02As03<E, Self>
+    where
+        Self: Sized,
+        E: Executor02,
+    {
+        Future02As03 {
+            exec,
+            locals: LocalMap::new(),
+            waker: WakerCache::default(),
+            v02: self,
+        }
+    }
+}
+
+// v0.2 futures are never pinned.
+impl<E, F> Unpin for Future02As03<E, F> {}
+
+impl<E, F> Future03 for Future02As03<E, F>
+where
+    F: Future02,
+    E: Executor02,
+{
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Self::Output> {
+        let this = self.get_mut();
+        let waker = this.waker.get(cx.waker());
+        let mut cx = Context02::new(&mut this.locals, waker, &mut this.exec);
+
+        match this.v02.poll(&mut cx) {
+            Ok(Async02::Ready(val)) => Poll03::Ready(Ok(val)),
+            Ok(Async02::Pending) => Poll03::Pending,
+            Err(err) => Poll03::Ready(Err(err)),
+        }
+    }
+}
+
+impl<S> Stream02Into03 for S
+where
+    S: Stream02,
+{
+    fn into_03_compat<E>(self, exec: E) -> Stream02As03<E, Self>
+    where
+        Self: Sized,
+        E: Executor02,
+    {
+        Stream02As03 {
+            exec,
+            locals: LocalMap::new(),
+            waker: WakerCache::default(),
+            v02: self,
+        }
+    }
+}
+
+impl<E, S> Unpin for Stream02As03<E, S> {}
+
+impl<E, S> Stream03 for Stream02As03<E, S>
+where
+    S: Stream02,
+    E: Executor02,
+{
+    type Item = Result<S::Item, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<Option<Self::Item>> {
+        let this = self.get_mut();
+        let waker = this.waker.get(cx.waker());
+        let mut cx = Context02::new(&mut this.locals, waker, &mut this.exec);
+
+        match this.v02.poll_next(&mut cx) {
+            Ok(Async02::Ready(Some(val))) => Poll03::Ready(Some(Ok(val))),
+            Ok(Async02::Ready(None)) => Poll03::Ready(None),
+            Ok(Async02::Pending) => Poll03::Pending,
+            Err(err) => Poll03::Ready(Some(Err(err))),
+        }
+    }
+}
+
+impl<F> Future03Into02 for F
+where
+    F: TryFuture03,
+{
+    fn into_02_compat(self) -> Future03As02<Self>
+    where
+        Self: Sized,
+    {
+        Future03As02 {
+            waker: WakerCache::default(),
+            v03: self,
+        }
+    }
+}
+
+impl<F> Future02 for Future03As02<F>
+where
+    F: TryFuture03 + Unpin,
+{
+    type Item = F::Ok;
+    type Error = F::Error;
+
+    fn poll(&mut self, cx: &mut Context02) -> Poll02<Self::Item, Self::Error> {
+        let mut cx = Context03::from_waker(self.waker.get(cx.waker()));
+
+        match Pin::new(&mut self.v03).try_poll(&mut cx) {
+            Poll03::Ready(Ok(val)) => Ok(Async02::Ready(val)),
+            Poll03::Ready(Err(err)) => Err(err),
+            Poll03::Pending => Ok(Async02::Pending),
+        }
+    }
+}
+
+impl<S> Stream03Into02 for S
+where
+    S: TryStream03,
+{
+    fn into_02_compat(self) -> Stream03As02<Self>
+    where
+        Self: Sized,
+    {
+        Stream03As02 {
+            waker: WakerCache::default(),
+            v03: self,
+        }
+    }
+}
+
+impl<S> Stream02 for Stream03As02<S>
+where
+    S: TryStream03 + Unpin,
+{
+    type Item = S::Ok;
+    type Error = S::Error;
+
+    fn poll_next(&mut self, cx: &mut Context02) -> Poll02<Option<Self::Item>, Self::Error> {
+        let mut cx = Context03::from_waker(self.waker.get(cx.waker()));
+
+        match Pin::new(&mut self.v03).try_poll_next(&mut cx) {
+            Poll03::Ready(Some(Ok(val))) => Ok(Async02::Ready(Some(val))),
+            Poll03::Ready(Some(Err(err))) => Err(err),
+            Poll03::Ready(None) => Ok(Async02::Ready(None)),
+            Poll03::Pending => Ok(Async02::Pending),
+        }
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I> TokioIntoAsyncIo03 for I {
+    fn into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncReadTk + AsyncWriteTk + Sized,
+    {
+        TokioAsAsyncIo03::new(self, DEFAULT_BUF_SIZE)
+    }
+
+    fn read_into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncReadTk + Sized,
+    {
+        TokioAsAsyncIo03::new(self, DEFAULT_BUF_SIZE)
+    }
+
+    fn write_into_io_03_compat(self) -> TokioAsAsyncIo03<Self>
+    where
+        Self: AsyncWriteTk + Sized,
+    {
+        TokioAsAsyncIo03::new(self, 0)
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I> TokioAsAsyncIo03<I> {
+    fn new(io: I, capacity: usize) -> TokioAsAsyncIo03<I> {
+        TokioAsAsyncIo03 {
+            v01: WakerTask::new(io),
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+}
+
+// tokio-io IOs are never pinned.
+#[cfg(feature = "io-tokio")]
+impl<I> Unpin for TokioAsAsyncIo03<I> {}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncReadTk> AsyncRead03 for TokioAsAsyncIo03<I> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context03, buf: &mut [u8]) -> Poll03<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.pos == this.filled && buf.len() >= this.buf.len() {
+            return into_poll03(this.v01.with_waker(cx.waker(), |io| io.poll_read(buf)));
+        }
+
+        let n = match Pin::new(&mut *this).poll_fill_buf(cx) {
+            Poll03::Ready(Ok(available)) => {
+                let n = cmp::min(available.len(), buf.len());
+                buf[..n].copy_from_slice(&available[..n]);
+                n
+            },
+            Poll03::Ready(Err(err)) => return Poll03::Ready(Err(err)),
+            Poll03::Pending => return Poll03::Pending,
+        };
+
+        this.pos += n;
+        Poll03::Ready(Ok(n))
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncReadTk> AsyncBufRead03 for TokioAsAsyncIo03<I> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.pos == this.filled {
+            let buf = &mut this.buf;
+            match this.v01.with_waker(cx.waker(), |io| io.poll_read(buf)) {
+                Ok(Async01::Ready(n)) => {
+                    this.pos = 0;
+                    this.filled = n;
+                },
+                Ok(Async01::NotReady) => return Poll03::Pending,
+                Err(err) => return Poll03::Ready(Err(err)),
+            }
+        }
+
+        Poll03::Ready(Ok(&this.buf[this.pos..this.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.pos = cmp::min(this.pos + amt, this.filled);
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncWriteTk> AsyncWrite03 for TokioAsAsyncIo03<I> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context03, buf: &[u8]) -> Poll03<io::Result<usize>> {
+        into_poll03(self.get_mut().v01.with_waker(cx.waker(), |io| io.poll_write(buf)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<io::Result<()>> {
+        into_poll03(self.get_mut().v01.with_waker(cx.waker(), |io| io.poll_flush()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context03) -> Poll03<io::Result<()>> {
+        into_poll03(self.get_mut().v01.with_waker(cx.waker(), |io| io.shutdown()))
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I> AsyncIo03IntoTokio for I {
+    fn io_03_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncRead03 + AsyncWrite03 + Unpin + Sized,
+    {
+        AsyncIo03AsTokio {
+            waker: CurrentWaker::default(),
+            v03: self,
+        }
+    }
+
+    fn io_03_read_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncRead03 + Unpin + Sized,
+    {
+        AsyncIo03AsTokio {
+            waker: CurrentWaker::default(),
+            v03: self,
+        }
+    }
+
+    fn io_03_write_into_tokio_compat(self) -> AsyncIo03AsTokio<Self>
+    where
+        Self: AsyncWrite03 + Unpin + Sized,
+    {
+        AsyncIo03AsTokio {
+            waker: CurrentWaker::default(),
+            v03: self,
+        }
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncRead03 + Unpin> io::Read for AsyncIo03AsTokio<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).poll_read(&mut cx, buf) {
+            Poll03::Ready(res) => res,
+            Poll03::Pending => Err(would_block()),
+        }
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncBufRead03 + Unpin> io::BufRead for AsyncIo03AsTokio<I> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).poll_fill_buf(&mut cx) {
+            Poll03::Ready(res) => res,
+            Poll03::Pending => Err(would_block()),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        Pin::new(&mut self.v03).consume(amt)
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncWrite03 + Unpin> io::Write for AsyncIo03AsTokio<I> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).poll_write(&mut cx, buf) {
+            Poll03::Ready(res) => res,
+            Poll03::Pending => Err(would_block()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v03).poll_flush(&mut cx) {
+            Poll03::Ready(res) => res,
+            Poll03::Pending => Err(would_block()),
+        }
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncRead03 + Unpin> AsyncReadTk for AsyncIo03AsTokio<I> {}
+
+#[cfg(feature = "io-tokio")]
+impl<I: AsyncWrite03 + Unpin> AsyncWriteTk for AsyncIo03AsTokio<I> {
+    fn shutdown(&mut self) -> Poll01<(), io::Error> {
+        let mut cx = Context03::from_waker(self.waker.current());
+
+        into_poll01(Pin::new(&mut self.v03).poll_close(&mut cx))
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+fn would_block() -> io::Error {
+    io::Error::from(io::ErrorKind::WouldBlock)
+}
+
+fn into_poll03<R, E>(poll: Poll01<R, E>) -> Poll03<Result<R, E>> {
+    match poll {
+        Ok(Async01::Ready(val)) => Poll03::Ready(Ok(val)),
+        Ok(Async01::NotReady) => Poll03::Pending,
+        Err(err) => Poll03::Ready(Err(err)),
+    }
+}
+
+#[cfg(any(feature = "sink", feature = "io-tokio"))]
+fn into_poll01<R, E>(poll: Poll03<Result<R, E>>) -> Poll01<R, E> {
+    match poll {
+        Poll03::Ready(Ok(val)) => Ok(Async01::Ready(val)),
+        Poll03::Ready(Err(err)) => Err(err),
+        Poll03::Pending => Ok(Async01::NotReady),
+    }
+}
@@ -10,9 +10,27 @@
 
 extern crate futures;
 extern crate futures_core;
+#[cfg(feature = "sink")]
 extern crate futures_sink;
+#[cfg(feature = "io-tokio")]
 extern crate futures_io;
+#[cfg(feature = "io-tokio")]
 extern crate tokio_io;
+#[cfg(feature = "futures-03")]
+extern crate futures_core_03;
+#[cfg(feature = "futures-03")]
+extern crate futures_io_03;
+#[cfg(feature = "futures-03")]
+extern crate futures_sink_03;
+#[cfg(feature = "tokio-1")]
+extern crate tokio_1 as tokio;
 
 pub mod futures_01;
 pub mod futures_02;
+#[cfg(feature = "futures-03")]
+pub mod futures_03;
+#[cfg(feature = "tokio-1")]
+pub mod tokio_1;
+
+#[cfg(any(feature = "futures-03", feature = "tokio-1"))]
+mod std_task;
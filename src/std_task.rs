@@ -0,0 +1,87 @@
+//! Glue between the v0.1 and v0.2 tasks and `std::task`.
+use std::sync::Arc;
+use std::task::{Wake as Wake03, Waker as Waker03};
+
+use futures_core::task::{Wake as Wake02};
+
+use super::futures_01::AnyWaker;
+use super::futures_02::Current;
+
+impl AnyWaker for Waker03 {
+    fn wake(&self) {
+        self.wake_by_ref();
+    }
+
+    fn will_wake(&self, other: &Waker03) -> bool {
+        Waker03::will_wake(self, other)
+    }
+}
+
+/// A waker of one version, for the waker of a context from the other.
+///
+/// Building one means allocating, so it's only rebuilt when polled with a
+/// waker for some other task.
+#[derive(Debug)]
+pub(crate) struct WakerCache<W, V> {
+    cached: Option<(W, V)>,
+}
+
+impl<W, V> Default for WakerCache<W, V> {
+    fn default() -> WakerCache<W, V> {
+        WakerCache {
+            cached: None,
+        }
+    }
+}
+
+impl<W, V> WakerCache<W, V>
+where
+    W: AnyWaker,
+    V: From<Arc<WakeWith<W>>>,
+{
+    pub(crate) fn get(&mut self, waker: &W) -> &V {
+        let stale = match self.cached {
+            Some((ref cached, _)) => !cached.will_wake(waker),
+            None => true,
+        };
+
+        if stale {
+            let converted = V::from(Arc::new(WakeWith(waker.clone())));
+            self.cached = Some((waker.clone(), converted));
+        }
+
+        match self.cached {
+            Some((_, ref converted)) => converted,
+            None => unreachable!("waker was just cached"),
+        }
+    }
+}
+
+/// Wakes a task through a waker of the other version.
+pub(crate) struct WakeWith<W>(W);
+
+impl<W: AnyWaker> Wake02 for WakeWith<W> {
+    fn wake(arc_self: &Arc<Self>) {
+        arc_self.0.wake();
+    }
+}
+
+impl<W: AnyWaker> Wake03 for WakeWith<W> {
+    fn wake(self: Arc<Self>) {
+        self.0.wake();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.wake();
+    }
+}
+
+impl Wake03 for Current {
+    fn wake(self: Arc<Self>) {
+        self.0.notify();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.notify();
+    }
+}
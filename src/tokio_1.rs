@@ -0,0 +1,497 @@
+//! tokio 1.x `AsyncRead`/`AsyncWrite` compatibility with tokio-io 0.1.x and
+//! futures-io 0.2.x.
+//!
+//! Requires the `tokio-1` feature.
+//!
+//! Buffers handed to a tokio 1.x reader are zeroed first, as tokio-io and
+//! futures-io do by default. Going the other way, a tokio-io or futures-io
+//! reader is given the unfilled part of a `ReadBuf` after it has been
+//! initialized, which `ReadBuf` only does once per buffer.
+//!
+//! The conversion methods are named apart from the ones in the other modules,
+//! so that all of the traits can be imported together.
+use std::io;
+use std::pin::Pin;
+use std::task::{Context as Context1, Poll as Poll1, Waker as Waker1};
+
+use futures::{Async as Async01, Poll as Poll01};
+
+use futures_core::{Async as Async02, Poll as Poll02};
+use futures_core::executor::{Executor as Executor02};
+use futures_core::task::{Context as Context02, LocalMap, Waker as Waker02};
+use futures_io::{AsyncRead as AsyncRead02, AsyncWrite as AsyncWrite02};
+
+use tokio_io::{AsyncRead as AsyncReadTk, AsyncWrite as AsyncWriteTk};
+
+use tokio::io::{AsyncRead as AsyncRead1, AsyncWrite as AsyncWrite1, ReadBuf};
+
+use super::futures_01::WakerTask;
+use super::futures_02::CurrentWaker;
+use super::std_task::WakerCache;
+
+/// Wrap an IO from tokio 1.x as an `AsyncRead`/`AsyncWrite` from tokio-io.
+///
+/// Like any tokio-io IO, it must be used from within a v0.1 task.
+#[derive(Debug)]
+pub struct Tokio1AsTokio<I> {
+    waker: CurrentWaker<Waker1>,
+    v1: I,
+}
+
+/// Wrap an IO from tokio-io as an `AsyncRead`/`AsyncWrite` from tokio 1.x.
+///
+/// The IO is driven in its own v0.1 task.
+#[derive(Debug)]
+pub struct TokioAsTokio1<I> {
+    v01: WakerTask<I, Waker1>,
+}
+
+/// Wrap an IO from tokio 1.x as an `AsyncRead`/`AsyncWrite` from v0.2.
+#[derive(Debug)]
+pub struct Tokio1AsAsyncIo02<I> {
+    waker: WakerCache<Waker02, Waker1>,
+    v1: I,
+}
+
+/// Wrap some `AsyncRead` or `AsyncWrite` from v0.2 as the same from tokio 1.x.
+#[derive(Debug)]
+pub struct AsyncIo02AsTokio1<E, I> {
+    exec: E,
+    locals: LocalMap,
+    waker: WakerCache<Waker1, Waker02>,
+    v02: I,
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from tokio 1.x into a [`Tokio1AsTokio`](Tokio1AsTokio).
+///
+/// Implemented for all types that implement tokio 1.x's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_tokio_01_compat` or
+/// `write_into_tokio_01_compat`.
+///
+/// The IO must be `Unpin`, so `!Unpin` IOs need to be put in a `Pin<Box<_>>` first.
+pub trait Tokio1IntoTokio {
+    /// Converts this IO into a `Tokio1AsTokio`.
+    fn into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncRead1 + AsyncWrite1 + Unpin + Sized;
+
+    /// Converts this read-only IO into a `Tokio1AsTokio`, which only
+    /// implements `AsyncRead`.
+    fn read_into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncRead1 + Unpin + Sized;
+
+    /// Converts this write-only IO into a `Tokio1AsTokio`, which only
+    /// implements `AsyncWrite`.
+    fn write_into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncWrite1 + Unpin + Sized;
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from tokio-io into a [`TokioAsTokio1`](TokioAsTokio1).
+///
+/// Implemented for all types that implement tokio-io's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_tokio_1_compat` or
+/// `write_into_tokio_1_compat`.
+pub trait TokioIntoTokio1 {
+    /// Converts this IO into a `TokioAsTokio1`.
+    fn into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncReadTk + AsyncWriteTk + Sized;
+
+    /// Converts this read-only IO into a `TokioAsTokio1`, which only
+    /// implements `AsyncRead`.
+    fn read_into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncReadTk + Sized;
+
+    /// Converts this write-only IO into a `TokioAsTokio1`, which only
+    /// implements `AsyncWrite`.
+    fn write_into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncWriteTk + Sized;
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from tokio 1.x into a [`Tokio1AsAsyncIo02`](Tokio1AsAsyncIo02).
+///
+/// Implemented for all types that implement tokio 1.x's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `read_into_io_02_compat` or
+/// `write_into_io_02_compat`.
+///
+/// The IO must be `Unpin`, so `!Unpin` IOs need to be put in a `Pin<Box<_>>` first.
+pub trait Tokio1IntoAsyncIo02 {
+    /// Converts this IO into a `Tokio1AsAsyncIo02`.
+    fn into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncRead1 + AsyncWrite1 + Unpin + Sized;
+
+    /// Converts this read-only IO into a `Tokio1AsAsyncIo02`, which only
+    /// implements `AsyncRead`.
+    fn read_into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncRead1 + Unpin + Sized;
+
+    /// Converts this write-only IO into a `Tokio1AsAsyncIo02`, which only
+    /// implements `AsyncWrite`.
+    fn write_into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncWrite1 + Unpin + Sized;
+}
+
+/// A trait to convert any `AsyncRead`/`AsyncWrite` from v0.2 into a [`AsyncIo02AsTokio1`](AsyncIo02AsTokio1).
+///
+/// Implemented for all types that implement v0.2's `AsyncRead`/`AsyncWrite` automatically.
+/// Types that only implement one of them can use `io_02_read_into_tokio_1_compat`
+/// or `io_02_write_into_tokio_1_compat`.
+pub trait AsyncIoIntoTokio1 {
+    /// Converts this IO into an `AsyncIo02AsTokio1`.
+    ///
+    /// An executor is required to allow this wrapped IO to still access
+    /// `Context::spawn` while wrapped.
+    fn io_02_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncRead02 + AsyncWrite02 + Sized,
+        E: Executor02;
+
+    /// Converts this read-only IO into an `AsyncIo02AsTokio1`, which only
+    /// implements `AsyncRead`.
+    ///
+    /// An executor is required to allow this wrapped IO to still access
+    /// `Context::spawn` while wrapped.
+    fn io_02_read_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncRead02 + Sized,
+        E: Executor02;
+
+    /// Converts this write-only IO into an `AsyncIo02AsTokio1`, which only
+    /// implements `AsyncWrite`.
+    ///
+    /// An executor is required to allow this wrapped IO to still access
+    /// `Context::spawn` while wrapped.
+    fn io_02_write_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncWrite02 + Sized,
+        E: Executor02;
+}
+
+impl<I> Tokio1IntoTokio for I {
+    fn into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncRead1 + AsyncWrite1 + Unpin + Sized,
+    {
+        Tokio1AsTokio {
+            waker: CurrentWaker::default(),
+            v1: self,
+        }
+    }
+
+    fn read_into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncRead1 + Unpin + Sized,
+    {
+        Tokio1AsTokio {
+            waker: CurrentWaker::default(),
+            v1: self,
+        }
+    }
+
+    fn write_into_tokio_01_compat(self) -> Tokio1AsTokio<Self>
+    where
+        Self: AsyncWrite1 + Unpin + Sized,
+    {
+        Tokio1AsTokio {
+            waker: CurrentWaker::default(),
+            v1: self,
+        }
+    }
+}
+
+impl<I: AsyncRead1 + Unpin> io::Read for Tokio1AsTokio<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut cx = Context1::from_waker(self.waker.current());
+        let mut buf = ReadBuf::new(buf);
+
+        match Pin::new(&mut self.v1).poll_read(&mut cx, &mut buf) {
+            Poll1::Ready(Ok(())) => Ok(buf.filled().len()),
+            Poll1::Ready(Err(err)) => Err(err),
+            Poll1::Pending => Err(would_block()),
+        }
+    }
+}
+
+impl<I: AsyncWrite1 + Unpin> io::Write for Tokio1AsTokio<I> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut cx = Context1::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v1).poll_write(&mut cx, buf) {
+            Poll1::Ready(res) => res,
+            Poll1::Pending => Err(would_block()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut cx = Context1::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v1).poll_flush(&mut cx) {
+            Poll1::Ready(res) => res,
+            Poll1::Pending => Err(would_block()),
+        }
+    }
+}
+
+impl<I: AsyncRead1 + Unpin> AsyncReadTk for Tokio1AsTokio<I> {}
+
+impl<I: AsyncWrite1 + Unpin> AsyncWriteTk for Tokio1AsTokio<I> {
+    fn shutdown(&mut self) -> Poll01<(), io::Error> {
+        let mut cx = Context1::from_waker(self.waker.current());
+
+        match Pin::new(&mut self.v1).poll_shutdown(&mut cx) {
+            Poll1::Ready(Ok(val)) => Ok(Async01::Ready(val)),
+            Poll1::Ready(Err(err)) => Err(err),
+            Poll1::Pending => Ok(Async01::NotReady),
+        }
+    }
+}
+
+impl<I> TokioIntoTokio1 for I {
+    fn into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncReadTk + AsyncWriteTk + Sized,
+    {
+        TokioAsTokio1 {
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn read_into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncReadTk + Sized,
+    {
+        TokioAsTokio1 {
+            v01: WakerTask::new(self),
+        }
+    }
+
+    fn write_into_tokio_1_compat(self) -> TokioAsTokio1<Self>
+    where
+        Self: AsyncWriteTk + Sized,
+    {
+        TokioAsTokio1 {
+            v01: WakerTask::new(self),
+        }
+    }
+}
+
+// tokio-io IOs are never pinned.
+impl<I> Unpin for TokioAsTokio1<I> {}
+
+impl<I: AsyncReadTk> AsyncRead1 for TokioAsTokio1<I> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context1, buf: &mut ReadBuf) -> Poll1<io::Result<()>> {
+        let res = {
+            let unfilled = buf.initialize_unfilled();
+            self.get_mut().v01.with_waker(cx.waker(), |io| io.read(unfilled))
+        };
+
+        match res {
+            Ok(n) => {
+                buf.advance(n);
+                Poll1::Ready(Ok(()))
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Poll1::Pending,
+            Err(err) => Poll1::Ready(Err(err)),
+        }
+    }
+}
+
+impl<I: AsyncWriteTk> AsyncWrite1 for TokioAsTokio1<I> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context1, buf: &[u8]) -> Poll1<io::Result<usize>> {
+        into_poll1(self.get_mut().v01.with_waker(cx.waker(), |io| io.write(buf)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context1) -> Poll1<io::Result<()>> {
+        into_poll1(self.get_mut().v01.with_waker(cx.waker(), |io| io.flush()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context1) -> Poll1<io::Result<()>> {
+        match self.get_mut().v01.with_waker(cx.waker(), |io| io.shutdown()) {
+            Ok(Async01::Ready(val)) => Poll1::Ready(Ok(val)),
+            Ok(Async01::NotReady) => Poll1::Pending,
+            Err(err) => Poll1::Ready(Err(err)),
+        }
+    }
+}
+
+impl<I> Tokio1IntoAsyncIo02 for I {
+    fn into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncRead1 + AsyncWrite1 + Unpin + Sized,
+    {
+        Tokio1AsAsyncIo02 {
+            waker: WakerCache::default(),
+            v1: self,
+        }
+    }
+
+    fn read_into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncRead1 + Unpin + Sized,
+    {
+        Tokio1AsAsyncIo02 {
+            waker: WakerCache::default(),
+            v1: self,
+        }
+    }
+
+    fn write_into_io_02_compat(self) -> Tokio1AsAsyncIo02<Self>
+    where
+        Self: AsyncWrite1 + Unpin + Sized,
+    {
+        Tokio1AsAsyncIo02 {
+            waker: WakerCache::default(),
+            v1: self,
+        }
+    }
+}
+
+impl<I: AsyncRead1 + Unpin> AsyncRead02 for Tokio1AsAsyncIo02<I> {
+    fn poll_read(&mut self, cx: &mut Context02, buf: &mut [u8]) -> Poll02<usize, io::Error> {
+        let mut cx = Context1::from_waker(self.waker.get(cx.waker()));
+        let mut buf = ReadBuf::new(buf);
+
+        match Pin::new(&mut self.v1).poll_read(&mut cx, &mut buf) {
+            Poll1::Ready(Ok(())) => Ok(Async02::Ready(buf.filled().len())),
+            Poll1::Ready(Err(err)) => Err(err),
+            Poll1::Pending => Ok(Async02::Pending),
+        }
+    }
+}
+
+impl<I: AsyncWrite1 + Unpin> AsyncWrite02 for Tokio1AsAsyncIo02<I> {
+    fn poll_write(&mut self, cx: &mut Context02, buf: &[u8]) -> Poll02<usize, io::Error> {
+        let mut cx = Context1::from_waker(self.waker.get(cx.waker()));
+
+        into_poll02(Pin::new(&mut self.v1).poll_write(&mut cx, buf))
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context02) -> Poll02<(), io::Error> {
+        let mut cx = Context1::from_waker(self.waker.get(cx.waker()));
+
+        into_poll02(Pin::new(&mut self.v1).poll_flush(&mut cx))
+    }
+
+    fn poll_close(&mut self, cx: &mut Context02) -> Poll02<(), io::Error> {
+        let mut cx = Context1::from_waker(self.waker.get(cx.waker()));
+
+        into_poll02(Pin::new(&mut self.v1).poll_shutdown(&mut cx))
+    }
+}
+
+impl<I> AsyncIoIntoTokio1 for I {
+    fn io_02_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncRead02 + AsyncWrite02 + Sized,
+        E: Executor02,
+    {
+        AsyncIo02AsTokio1 {
+            exec,
+            locals: LocalMap::new(),
+            waker: WakerCache::default(),
+            v02: self,
+        }
+    }
+
+    fn io_02_read_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncRead02 + Sized,
+        E: Executor02,
+    {
+        AsyncIo02AsTokio1 {
+            exec,
+            locals: LocalMap::new(),
+            waker: WakerCache::default(),
+            v02: self,
+        }
+    }
+
+    fn io_02_write_into_tokio_1_compat<E>(self, exec: E) -> AsyncIo02AsTokio1<E, Self>
+    where
+        Self: AsyncWrite02 + Sized,
+        E: Executor02,
+    {
+        AsyncIo02AsTokio1 {
+            exec,
+            locals: LocalMap::new(),
+            waker: WakerCache::default(),
+            v02: self,
+        }
+    }
+}
+
+// v0.2 IOs are never pinned.
+impl<E, I> Unpin for AsyncIo02AsTokio1<E, I> {}
+
+impl<E: Executor02, I: AsyncRead02> AsyncRead1 for AsyncIo02AsTokio1<E, I> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context1, buf: &mut ReadBuf) -> Poll1<io::Result<()>> {
+        let this = self.get_mut();
+        let mut cx = Context02::new(&mut this.locals, this.waker.get(cx.waker()), &mut this.exec);
+
+        match this.v02.poll_read(&mut cx, buf.initialize_unfilled()) {
+            Ok(Async02::Ready(n)) => {
+                buf.advance(n);
+                Poll1::Ready(Ok(()))
+            },
+            Ok(Async02::Pending) => Poll1::Pending,
+            Err(err) => Poll1::Ready(Err(err)),
+        }
+    }
+}
+
+impl<E: Executor02, I: AsyncWrite02> AsyncWrite1 for AsyncIo02AsTokio1<E, I> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context1, buf: &[u8]) -> Poll1<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut cx = Context02::new(&mut this.locals, this.waker.get(cx.waker()), &mut this.exec);
+
+        from_poll02(this.v02.poll_write(&mut cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context1) -> Poll1<io::Result<()>> {
+        let this = self.get_mut();
+        let mut cx = Context02::new(&mut this.locals, this.waker.get(cx.waker()), &mut this.exec);
+
+        from_poll02(this.v02.poll_flush(&mut cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context1) -> Poll1<io::Result<()>> {
+        let this = self.get_mut();
+        let mut cx = Context02::new(&mut this.locals, this.waker.get(cx.waker()), &mut this.exec);
+
+        from_poll02(this.v02.poll_close(&mut cx))
+    }
+}
+
+fn would_block() -> io::Error {
+    io::Error::from(io::ErrorKind::WouldBlock)
+}
+
+fn into_poll1<R>(res: io::Result<R>) -> Poll1<io::Result<R>> {
+    match res {
+        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Poll1::Pending,
+        res => Poll1::Ready(res),
+    }
+}
+
+fn into_poll02<R>(poll: Poll1<io::Result<R>>) -> Poll02<R, io::Error> {
+    match poll {
+        Poll1::Ready(Ok(val)) => Ok(Async02::Ready(val)),
+        Poll1::Ready(Err(err)) => Err(err),
+        Poll1::Pending => Ok(Async02::Pending),
+    }
+}
+
+fn from_poll02<R>(poll: Poll02<R, io::Error>) -> Poll1<io::Result<R>> {
+    match poll {
+        Ok(Async02::Ready(val)) => Poll1::Ready(Ok(val)),
+        Ok(Async02::Pending) => Poll1::Pending,
+        Err(err) => Poll1::Ready(Err(err)),
+    }
+}
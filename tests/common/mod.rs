@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests and benchmarks.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use futures_core::{Future as Future02, Never};
+use futures_core::executor::{Executor as Executor02, SpawnError};
+use futures_core::task::{Context, LocalMap, Wake, Waker};
+
+/// A v0.2 `Wake` that does nothing.
+pub struct NoopWake;
+
+impl Wake for NoopWake {
+    fn wake(_: &Arc<Self>) {}
+}
+
+/// A v0.2 `Waker` that does nothing.
+pub fn noop_waker() -> Waker {
+    Waker::from(Arc::new(NoopWake))
+}
+
+/// Runs `f` with a v0.2 `Context` woken by `waker`, that can't spawn.
+pub fn with_context<F, R>(waker: &Waker, f: F) -> R
+where
+    F: FnOnce(&mut Context) -> R,
+{
+    let mut locals = LocalMap::new();
+    let mut cx = Context::without_spawn(&mut locals, waker);
+    f(&mut cx)
+}
+
+/// A v0.2 `Executor` that rejects every future.
+pub struct NoSpawn;
+
+impl Executor02 for NoSpawn {
+    fn spawn(&mut self, _: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        Err(SpawnError::shutdown())
+    }
+}
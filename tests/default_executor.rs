@@ -0,0 +1,84 @@
+extern crate futures_compat;
+extern crate futures_core;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures_compat::futures_02::{set_default_executor, DefaultExecutor02};
+use futures_core::{Future as Future02, Never};
+use futures_core::executor::{Executor as Executor02, SpawnError};
+use futures_core::future;
+
+/// Counts the futures spawned onto it, without running them.
+#[derive(Clone, Default)]
+struct Count(Arc<AtomicUsize>);
+
+impl Count {
+    fn get(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Executor02 for Count {
+    fn spawn(&mut self, _: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn spawn() -> Result<(), SpawnError> {
+    DefaultExecutor02.spawn(Box::new(future::ok::<(), Never>(())))
+}
+
+#[test]
+fn spawn_without_default_fails() {
+    assert!(spawn().is_err());
+    assert!(DefaultExecutor02.status().is_err());
+}
+
+#[test]
+fn nested_guards_restore_previous_default() {
+    let (one, two) = (Count::default(), Count::default());
+
+    let guard_one = set_default_executor(one.clone());
+    spawn().unwrap();
+    {
+        let _guard_two = set_default_executor(two.clone());
+        spawn().unwrap();
+    }
+    spawn().unwrap();
+    drop(guard_one);
+
+    assert!(spawn().is_err());
+    assert_eq!((one.get(), two.get()), (2, 1));
+}
+
+#[test]
+#[should_panic(expected = "out of order")]
+fn out_of_order_drop_panics() {
+    let guard_one = set_default_executor(Count::default());
+    let _guard_two = set_default_executor(Count::default());
+    drop(guard_one);
+}
+
+#[test]
+fn out_of_order_drop_while_panicking_unsets_newer_defaults() {
+    let (one, two, three) = (Count::default(), Count::default(), Count::default());
+    let _guard_one = set_default_executor(one.clone());
+    let mut guard_three = None;
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard_two = set_default_executor(two.clone());
+        guard_three = Some(set_default_executor(three.clone()));
+        panic!("unwinding past a newer guard");
+    }));
+    assert!(res.is_err());
+
+    // Dropping the second guard unset the third's executor too, and
+    // dropping the third guard afterwards doesn't touch the first's.
+    spawn().unwrap();
+    drop(guard_three);
+    spawn().unwrap();
+    assert_eq!((one.get(), two.get(), three.get()), (2, 0, 0));
+}
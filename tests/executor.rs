@@ -0,0 +1,111 @@
+extern crate futures;
+extern crate futures_compat;
+extern crate futures_core;
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::Future as Future01;
+use futures::future::{self, Executor as Executor01, ExecuteError as ExecuteError01, ExecuteErrorKind};
+use futures_compat::futures_01::{BoxedExecutorInto02, ExecutorInto02};
+use futures_compat::futures_02::ExecutorInto01;
+use futures_core::{Async as Async02, Future as Future02, Never, Poll as Poll02};
+use futures_core::executor::{Executor as Executor02, SpawnError};
+use futures_core::task::Context;
+
+/// A v0.2 executor that polls each future once, as soon as it's spawned.
+struct Inline;
+
+impl Executor02 for Inline {
+    fn spawn(&mut self, mut f: Box<dyn Future02<Item=(), Error=Never> + Send>) -> Result<(), SpawnError> {
+        common::with_context(&common::noop_waker(), |cx| {
+            let _ = f.poll(cx);
+        });
+        Ok(())
+    }
+}
+
+/// A v0.1 executor that is always at capacity.
+struct Full;
+
+impl<F: Future01<Item=(), Error=()>> Executor01<F> for Full {
+    fn execute(&self, f: F) -> Result<(), ExecuteError01<F>> {
+        Err(ExecuteError01::new(ExecuteErrorKind::NoCapacity, f))
+    }
+}
+
+/// A v0.2 future that records that it ran.
+struct Run(Arc<AtomicBool>);
+
+impl Future02 for Run {
+    type Item = ();
+    type Error = Never;
+
+    fn poll(&mut self, _: &mut Context) -> Poll02<(), Never> {
+        self.0.store(true, Ordering::SeqCst);
+        Ok(Async02::Ready(()))
+    }
+}
+
+#[test]
+fn try_spawn_gives_back_rejected_future() {
+    let ran = Arc::new(AtomicBool::new(false));
+    let exec = Full.into_02_compat();
+
+    let err = exec.try_spawn(Box::new(Run(ran.clone()))).unwrap_err();
+    assert_eq!(err.kind(), ExecuteErrorKind::NoCapacity);
+    assert!(!ran.load(Ordering::SeqCst));
+
+    let mut f = err.into_future();
+    common::with_context(&common::noop_waker(), |cx| {
+        assert_eq!(f.poll(cx), Ok(Async02::Ready(())));
+    });
+    assert!(ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn try_spawn_boxed_returns_error_kind() {
+    let exec = Full.into_02_compat_boxed();
+    let ran = Arc::new(AtomicBool::new(false));
+
+    assert_eq!(exec.try_spawn(Box::new(Run(ran.clone()))), Err(ExecuteErrorKind::NoCapacity));
+    assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn rejected_future_is_given_back_by_02_executor() {
+    let ran = Arc::new(AtomicBool::new(false));
+    let flag = ran.clone();
+    let exec = common::NoSpawn.into_01_compat();
+
+    let err = exec.execute(future::lazy(move || {
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+    })).unwrap_err();
+    assert_eq!(err.kind(), ExecuteErrorKind::Shutdown);
+    assert!(!ran.load(Ordering::SeqCst));
+
+    err.into_future().wait().unwrap();
+    assert!(ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn reentrant_spawn_onto_02_executor_fails() {
+    let exec = Arc::new(Inline.into_01_compat());
+    let inner = exec.clone();
+    let spawned = Arc::new(Mutex::new(None));
+    let result = spawned.clone();
+
+    exec.execute(future::lazy(move || {
+        let res = inner.execute(future::ok(()));
+        *result.lock().unwrap() = Some(res.map_err(|err| err.kind()));
+        Ok(())
+    })).unwrap();
+
+    assert_eq!(*spawned.lock().unwrap(), Some(Err(ExecuteErrorKind::Shutdown)));
+
+    // Spawning from outside of the executor still works.
+    assert!(exec.execute(future::ok(())).is_ok());
+}
@@ -0,0 +1,376 @@
+#![cfg(feature = "futures-03")]
+
+extern crate futures;
+extern crate futures_compat;
+extern crate futures_core;
+extern crate futures_core_03;
+
+mod common;
+
+use std::future::{self as future03, Future as Future03};
+use std::pin::Pin;
+use std::task::{Context as Context03, Poll as Poll03, Waker as Waker03};
+use std::vec;
+
+use futures::{Future as Future01, Stream as Stream01};
+use futures::future;
+use futures::stream;
+use futures_compat::futures_03::{
+    Future01Into03, Future02Into03, Future03Into01, Future03Into02, Stream01Into03, Stream02Into03,
+    Stream03Into01, Stream03Into02,
+};
+use futures_core::{Async as Async02, Future as Future02, Never, Poll as Poll02, Stream as Stream02};
+use futures_core::future as future02;
+use futures_core::task::{Context as Context02};
+use futures_core_03::Stream as Stream03;
+
+/// A v0.3 stream of the items in a `Vec`.
+struct Iter03(vec::IntoIter<u32>);
+
+impl Stream03 for Iter03 {
+    type Item = Result<u32, ()>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context03) -> Poll03<Option<Self::Item>> {
+        Poll03::Ready(self.0.next().map(Ok))
+    }
+}
+
+/// A v0.2 stream of the items in a `Vec`.
+struct Iter02(vec::IntoIter<u32>);
+
+impl Stream02 for Iter02 {
+    type Item = u32;
+    type Error = Never;
+
+    fn poll_next(&mut self, _: &mut Context02) -> Poll02<Option<u32>, Never> {
+        Ok(Async02::Ready(self.0.next()))
+    }
+}
+
+/// Polls a v0.3 future once, expecting it to be ready.
+fn ready<F: Future03 + Unpin>(mut fut: F) -> F::Output {
+    let mut cx = Context03::from_waker(Waker03::noop());
+    match Pin::new(&mut fut).poll(&mut cx) {
+        Poll03::Ready(val) => val,
+        Poll03::Pending => panic!("future was pending"),
+    }
+}
+
+/// Polls a v0.3 stream until it ends, expecting it never to be pending.
+fn collect<S: Stream03 + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let mut cx = Context03::from_waker(Waker03::noop());
+    let mut items = Vec::new();
+    loop {
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll03::Ready(Some(item)) => items.push(item),
+            Poll03::Ready(None) => return items,
+            Poll03::Pending => panic!("stream was pending"),
+        }
+    }
+}
+
+/// Polls a v0.2 future once, expecting it to be ready.
+fn ready_02<F: Future02>(mut fut: F) -> Result<F::Item, F::Error> {
+    common::with_context(&common::noop_waker(), |cx| match fut.poll(cx) {
+        Ok(Async02::Ready(val)) => Ok(val),
+        Ok(Async02::Pending) => panic!("future was pending"),
+        Err(err) => Err(err),
+    })
+}
+
+/// Polls a v0.2 stream until it ends, expecting it never to be pending.
+fn collect_02<S: Stream02>(mut stream: S) -> Result<Vec<S::Item>, S::Error> {
+    common::with_context(&common::noop_waker(), |cx| {
+        let mut items = Vec::new();
+        loop {
+            match stream.poll_next(cx)? {
+                Async02::Ready(Some(item)) => items.push(item),
+                Async02::Ready(None) => return Ok(items),
+                Async02::Pending => panic!("stream was pending"),
+            }
+        }
+    })
+}
+
+#[test]
+fn future_01_round_trip() {
+    let fut = future::ok::<u32, ()>(7).into_03_compat().into_01_compat();
+    assert_eq!(fut.wait(), Ok(7));
+
+    let fut = future::err::<u32, u32>(3).into_03_compat().into_01_compat();
+    assert_eq!(fut.wait(), Err(3));
+}
+
+#[test]
+fn future_03_round_trip() {
+    let fut = future03::ready(Ok::<u32, ()>(7)).into_01_compat().into_03_compat();
+    assert_eq!(ready(fut), Ok(7));
+
+    let fut = future03::ready(Err::<u32, u32>(3)).into_01_compat().into_03_compat();
+    assert_eq!(ready(fut), Err(3));
+}
+
+#[test]
+fn stream_01_round_trip() {
+    let stream = stream::iter_ok::<_, ()>(vec![1, 2, 3]).into_03_compat().into_01_compat();
+    assert_eq!(stream.collect().wait(), Ok(vec![1, 2, 3]));
+}
+
+#[test]
+fn stream_03_round_trip() {
+    let stream = Iter03(vec![1, 2, 3].into_iter()).into_01_compat().into_03_compat();
+    assert_eq!(collect(stream), vec![Ok(1), Ok(2), Ok(3)]);
+}
+
+#[test]
+fn future_02_round_trip() {
+    let fut = future02::ok::<u32, ()>(7).into_03_compat(common::NoSpawn).into_02_compat();
+    assert_eq!(ready_02(fut), Ok(7));
+
+    let fut = future02::err::<u32, u32>(3).into_03_compat(common::NoSpawn).into_02_compat();
+    assert_eq!(ready_02(fut), Err(3));
+}
+
+#[test]
+fn future_03_round_trip_through_02() {
+    let fut = future03::ready(Ok::<u32, ()>(7)).into_02_compat().into_03_compat(common::NoSpawn);
+    assert_eq!(ready(fut), Ok(7));
+
+    let fut = future03::ready(Err::<u32, u32>(3)).into_02_compat().into_03_compat(common::NoSpawn);
+    assert_eq!(ready(fut), Err(3));
+}
+
+#[test]
+fn stream_02_round_trip() {
+    let stream = Iter02(vec![1, 2, 3].into_iter()).into_03_compat(common::NoSpawn).into_02_compat();
+    assert_eq!(collect_02(stream).unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn stream_03_round_trip_through_02() {
+    let stream = Iter03(vec![1, 2, 3].into_iter()).into_02_compat().into_03_compat(common::NoSpawn);
+    assert_eq!(collect(stream), vec![Ok(1), Ok(2), Ok(3)]);
+}
+
+#[cfg(feature = "sink")]
+mod sink {
+    extern crate futures_sink_03;
+
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use futures::{Async as Async01, AsyncSink as AsyncSink01, Poll as Poll01, Sink as Sink01, StartSend as StartSend01};
+    use futures_compat::futures_03::{Sink01Into03, Sink03Into01};
+    use self::futures_sink_03::Sink as Sink03;
+
+    #[derive(Debug, Default)]
+    struct Recorded {
+        items: Vec<u32>,
+        closed: bool,
+    }
+
+    /// A v0.1 sink that records what it's sent, and whether it was closed.
+    struct Record(Rc<RefCell<Recorded>>);
+
+    impl Sink01 for Record {
+        type SinkItem = u32;
+        type SinkError = ();
+
+        fn start_send(&mut self, item: u32) -> StartSend01<u32, ()> {
+            let mut rec = self.0.borrow_mut();
+            assert!(!rec.closed, "sent after close");
+            rec.items.push(item);
+            Ok(AsyncSink01::Ready)
+        }
+
+        fn poll_complete(&mut self) -> Poll01<(), ()> {
+            Ok(Async01::Ready(()))
+        }
+
+        fn close(&mut self) -> Poll01<(), ()> {
+            self.0.borrow_mut().closed = true;
+            Ok(Async01::Ready(()))
+        }
+    }
+
+    #[test]
+    fn sink_01_round_trip_sends_and_closes() {
+        let rec = Rc::new(RefCell::new(Recorded::default()));
+        let mut sink = Record(rec.clone()).sink_into_03_compat().sink_into_01_compat();
+
+        for i in 1..4 {
+            sink = sink.send(i).wait().unwrap();
+        }
+        assert_eq!(rec.borrow().items, vec![1, 2, 3]);
+        assert!(!rec.borrow().closed);
+
+        future::poll_fn(|| sink.close()).wait().unwrap();
+        assert!(rec.borrow().closed);
+    }
+
+    /// A v0.3 sink that records what it's sent, and whether it was closed.
+    struct Record03(Rc<RefCell<Recorded>>);
+
+    impl Sink03<u32> for Record03 {
+        type Error = ();
+
+        fn poll_ready(self: Pin<&mut Self>, _: &mut Context03) -> Poll03<Result<(), ()>> {
+            Poll03::Ready(Ok(()))
+        }
+
+        fn start_send(self: Pin<&mut Self>, item: u32) -> Result<(), ()> {
+            let mut rec = self.0.borrow_mut();
+            assert!(!rec.closed, "sent after close");
+            rec.items.push(item);
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context03) -> Poll03<Result<(), ()>> {
+            Poll03::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context03) -> Poll03<Result<(), ()>> {
+            self.0.borrow_mut().closed = true;
+            Poll03::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn sink_03_round_trip_sends_and_closes() {
+        let rec = Rc::new(RefCell::new(Recorded::default()));
+        let mut sink = Record03(rec.clone()).sink_into_01_compat().sink_into_03_compat();
+        let mut cx = Context03::from_waker(Waker03::noop());
+
+        for i in 1..4 {
+            assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll03::Ready(Ok(())));
+            Pin::new(&mut sink).start_send(i).unwrap();
+        }
+        assert_eq!(Pin::new(&mut sink).poll_flush(&mut cx), Poll03::Ready(Ok(())));
+        assert_eq!(rec.borrow().items, vec![1, 2, 3]);
+        assert!(!rec.borrow().closed);
+
+        assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll03::Ready(Ok(())));
+        assert!(rec.borrow().closed);
+    }
+}
+
+#[cfg(feature = "io-tokio")]
+mod io {
+    extern crate futures_io_03;
+    extern crate tokio_io;
+
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::io::{self, Read};
+    use std::rc::Rc;
+
+    use futures_compat::futures_03::TokioIntoAsyncIo03;
+    use self::futures_io_03::{AsyncBufRead as AsyncBufRead03, AsyncRead as AsyncRead03};
+    use self::tokio_io::AsyncRead as AsyncReadTk;
+
+    /// The size of the buffer in `TokioAsAsyncIo03`.
+    const BUF_SIZE: usize = 8 * 1024;
+
+    /// Reads at most `limit` bytes at a time from counting bytes, recording
+    /// the length of each buffer it's given.
+    struct Chunks {
+        pos: usize,
+        limit: usize,
+        lens: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.lens.borrow_mut().push(buf.len());
+            let n = buf.len().min(self.limit);
+            for b in &mut buf[..n] {
+                *b = self.pos as u8;
+                self.pos += 1;
+            }
+            Ok(n)
+        }
+    }
+
+    impl AsyncReadTk for Chunks {}
+
+    /// The bytes `Chunks` reads, starting at `pos`.
+    fn expected(pos: usize, len: usize) -> Vec<u8> {
+        (pos..pos + len).map(|i| i as u8).collect()
+    }
+
+    fn read<R: AsyncRead03 + Unpin>(io: &mut R, len: usize) -> Vec<u8> {
+        let mut cx = Context03::from_waker(Waker03::noop());
+        let mut buf = vec![0; len];
+        match Pin::new(io).poll_read(&mut cx, &mut buf) {
+            Poll03::Ready(Ok(n)) => {
+                buf.truncate(n);
+                buf
+            },
+            Poll03::Ready(Err(err)) => panic!("read failed: {}", err),
+            Poll03::Pending => panic!("read was pending"),
+        }
+    }
+
+    fn fill_buf<R: AsyncBufRead03 + Unpin>(io: &mut R) -> Vec<u8> {
+        let mut cx = Context03::from_waker(Waker03::noop());
+        match Pin::new(io).poll_fill_buf(&mut cx) {
+            Poll03::Ready(Ok(buf)) => buf.to_vec(),
+            Poll03::Ready(Err(err)) => panic!("fill_buf failed: {}", err),
+            Poll03::Pending => panic!("fill_buf was pending"),
+        }
+    }
+
+    #[test]
+    fn small_reads_go_through_buffer() {
+        let lens = Rc::new(RefCell::new(Vec::new()));
+        let mut io = Chunks { pos: 0, limit: 3000, lens: lens.clone() }.read_into_io_03_compat();
+
+        // The first read fills the buffer, and later ones are served from it.
+        assert_eq!(read(&mut io, 10), expected(0, 10));
+        assert_eq!(read(&mut io, 100), expected(10, 100));
+        assert_eq!(*lens.borrow(), vec![BUF_SIZE]);
+
+        // A large read only gets what's left in the buffer.
+        assert_eq!(read(&mut io, 2 * BUF_SIZE), expected(110, 2890));
+        assert_eq!(*lens.borrow(), vec![BUF_SIZE]);
+    }
+
+    #[test]
+    fn large_reads_bypass_empty_buffer() {
+        let lens = Rc::new(RefCell::new(Vec::new()));
+        let mut io = Chunks { pos: 0, limit: 3 * BUF_SIZE, lens: lens.clone() }.read_into_io_03_compat();
+
+        assert_eq!(read(&mut io, 2 * BUF_SIZE), expected(0, 2 * BUF_SIZE));
+        assert_eq!(read(&mut io, BUF_SIZE), expected(2 * BUF_SIZE, BUF_SIZE));
+        assert_eq!(*lens.borrow(), vec![2 * BUF_SIZE, BUF_SIZE]);
+
+        // Smaller reads still use the buffer.
+        assert_eq!(read(&mut io, 10), expected(3 * BUF_SIZE, 10));
+        assert_eq!(*lens.borrow(), vec![2 * BUF_SIZE, BUF_SIZE, BUF_SIZE]);
+    }
+
+    #[test]
+    fn reads_mixed_with_fill_buf_and_consume() {
+        let lens = Rc::new(RefCell::new(Vec::new()));
+        let mut io = Chunks { pos: 0, limit: 3000, lens: lens.clone() }.read_into_io_03_compat();
+
+        assert_eq!(fill_buf(&mut io), expected(0, 3000));
+        Pin::new(&mut io).consume(1000);
+        assert_eq!(read(&mut io, 10), expected(1000, 10));
+        assert_eq!(fill_buf(&mut io), expected(1010, 1990));
+        assert_eq!(*lens.borrow(), vec![BUF_SIZE]);
+
+        // Consuming more than is buffered only empties the buffer.
+        Pin::new(&mut io).consume(5000);
+        assert_eq!(fill_buf(&mut io), expected(3000, 3000));
+        assert_eq!(*lens.borrow(), vec![BUF_SIZE, BUF_SIZE]);
+
+        // Once it's empty, a large read bypasses it.
+        Pin::new(&mut io).consume(3000);
+        assert_eq!(read(&mut io, BUF_SIZE + 1), expected(6000, 3000));
+        assert_eq!(*lens.borrow(), vec![BUF_SIZE, BUF_SIZE, BUF_SIZE + 1]);
+    }
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "sink")]
+
+extern crate futures;
+extern crate futures_compat;
+extern crate futures_core;
+extern crate futures_sink;
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::{Async as Async01, AsyncSink as AsyncSink01, Poll as Poll01, Sink as Sink01, StartSend as StartSend01, Stream as Stream01};
+use futures::sync::mpsc::{self, SendError, Sender};
+use futures_compat::futures_01::{Sink01As02Error, SinkInto02};
+use futures_core::Async as Async02;
+use futures_core::task::{Wake, Waker};
+use futures_sink::Sink as Sink02;
+
+struct CountWake(AtomicUsize);
+
+impl Wake for CountWake {
+    fn wake(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A v0.1 sink that hangs up its channel when closed, so the receiver sees
+/// the end of the stream.
+struct HangUp<T>(Option<Sender<T>>);
+
+impl<T> Sink01 for HangUp<T> {
+    type SinkItem = T;
+    type SinkError = SendError<T>;
+
+    fn start_send(&mut self, item: T) -> StartSend01<T, SendError<T>> {
+        self.0.as_mut().expect("sink was closed").start_send(item)
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), SendError<T>> {
+        self.0.as_mut().expect("sink was closed").poll_complete()
+    }
+
+    fn close(&mut self) -> Poll01<(), SendError<T>> {
+        match self.poll_complete()? {
+            Async01::Ready(()) => {
+                self.0 = None;
+                Ok(Async01::Ready(()))
+            },
+            Async01::NotReady => Ok(Async01::NotReady),
+        }
+    }
+}
+
+/// A v0.1 sink that records what it's sent, and fails on one item, giving it
+/// back in the error.
+struct FailOn {
+    item: u32,
+    sent: Rc<RefCell<Vec<u32>>>,
+}
+
+impl Sink01 for FailOn {
+    type SinkItem = u32;
+    type SinkError = u32;
+
+    fn start_send(&mut self, item: u32) -> StartSend01<u32, u32> {
+        if item == self.item {
+            return Err(item);
+        }
+        self.sent.borrow_mut().push(item);
+        Ok(AsyncSink01::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll01<(), u32> {
+        Ok(Async01::Ready(()))
+    }
+}
+
+#[test]
+fn close_delivers_buffered_items_and_ends_stream() {
+    common::with_context(&common::noop_waker(), |cx| {
+        let (tx, rx) = mpsc::channel(8);
+        let mut sink = HangUp(Some(tx)).sink_into_02_compat();
+
+        for i in 0..3 {
+            assert_eq!(sink.poll_ready(cx).unwrap(), Async02::Ready(()));
+            sink.start_send(i).unwrap();
+        }
+        assert_eq!(sink.buffered_len(), 3);
+
+        assert_eq!(sink.poll_close(cx).unwrap(), Async02::Ready(()));
+        assert_eq!(sink.buffered_len(), 0);
+
+        let received = rx.wait().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(received, vec![0, 1, 2]);
+
+        match sink.start_send(3) {
+            Err(Sink01As02Error::Closed) => (),
+            other => panic!("expected Closed, got {:?}", other),
+        }
+        match sink.poll_ready(cx) {
+            Err(Sink01As02Error::Closed) => (),
+            other => panic!("expected Closed, got {:?}", other),
+        }
+    })
+}
+
+#[test]
+fn bounded_poll_ready_is_pending_at_capacity() {
+    let wake = Arc::new(CountWake(AtomicUsize::new(0)));
+    common::with_context(&Waker::from(wake.clone()), |cx| {
+        // With no buffer, the channel takes one item and then parks the sender
+        // until it's received.
+        let (tx, rx) = mpsc::channel(0);
+        let mut sink = tx.sink_into_02_compat_bounded(1);
+        assert_eq!(sink.capacity(), Some(1));
+
+        assert_eq!(sink.poll_ready(cx).unwrap(), Async02::Ready(()));
+        sink.start_send(0).unwrap();
+        assert_eq!(sink.poll_ready(cx).unwrap(), Async02::Ready(()));
+        sink.start_send(1).unwrap();
+
+        assert_eq!(sink.poll_ready(cx).unwrap(), Async02::Pending);
+        assert_eq!(sink.buffered_len(), 1);
+        assert_eq!(wake.0.load(Ordering::SeqCst), 0);
+
+        let mut rx = rx.wait();
+        assert_eq!(rx.next(), Some(Ok(0)));
+        assert_eq!(wake.0.load(Ordering::SeqCst), 1);
+
+        assert_eq!(sink.poll_ready(cx).unwrap(), Async02::Ready(()));
+        assert_eq!(sink.buffered_len(), 0);
+        assert_eq!(rx.next(), Some(Ok(1)));
+    })
+}
+
+#[test]
+fn take_buffered_after_failed_flush_returns_unsent_items() {
+    common::with_context(&common::noop_waker(), |cx| {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let mut sink = FailOn { item: 2, sent: sent.clone() }.sink_into_02_compat();
+
+        for i in 0..5 {
+            sink.start_send(i).unwrap();
+        }
+
+        match sink.poll_flush(cx) {
+            Err(Sink01As02Error::Sink(2)) => (),
+            other => panic!("expected Sink(2), got {:?}", other),
+        }
+        assert_eq!(*sent.borrow(), vec![0, 1]);
+
+        assert_eq!(sink.take_buffered(), vec![3, 4]);
+        assert_eq!(sink.buffered_len(), 0);
+    })
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "tokio-1")]
+
+extern crate futures;
+extern crate futures_compat;
+extern crate futures_core;
+extern crate futures_io;
+extern crate tokio_1;
+extern crate tokio_io;
+
+mod common;
+
+use std::cell::Cell;
+use std::io::{self, Cursor, Read};
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context as Context1, Poll as Poll1, Waker as Waker1};
+
+use futures::Future as Future01;
+use futures::future;
+use futures_compat::tokio_1::{AsyncIoIntoTokio1, Tokio1IntoAsyncIo02, Tokio1IntoTokio, TokioIntoTokio1};
+use futures_core::Async as Async02;
+use futures_core::task::{Context as Context02};
+use futures_io::{AsyncRead as AsyncRead02};
+use tokio_1::io::{AsyncRead as AsyncRead1, ReadBuf};
+use tokio_io::{AsyncRead as AsyncReadTk};
+
+const DATA: &[u8] = b"a round trip through tokio 1";
+
+/// Reads one byte, recording the first byte of the buffer it was given.
+struct Inspect(Rc<Cell<Option<u8>>>);
+
+impl Read for Inspect {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.set(Some(buf[0]));
+        buf[0] = b'x';
+        Ok(1)
+    }
+}
+
+impl AsyncReadTk for Inspect {}
+
+impl AsyncRead02 for Inspect {
+    fn poll_read(&mut self, _: &mut Context02, buf: &mut [u8]) -> futures_core::Poll<usize, io::Error> {
+        self.read(buf).map(Async02::Ready)
+    }
+}
+
+/// Reads once from a tokio 1.x reader into memory that is initialized, but
+/// that the `ReadBuf` treats as uninitialized, returning what was read and
+/// how much of the buffer is initialized afterwards.
+fn read_once<R: AsyncRead1 + Unpin>(reader: &mut R) -> (Vec<u8>, usize) {
+    let mut mem = [MaybeUninit::new(0xAA); 4];
+    let mut buf = ReadBuf::uninit(&mut mem);
+    let mut cx = Context1::from_waker(Waker1::noop());
+
+    match Pin::new(reader).poll_read(&mut cx, &mut buf) {
+        Poll1::Ready(Ok(())) => (buf.filled().to_vec(), buf.initialized().len()),
+        Poll1::Ready(Err(err)) => panic!("read failed: {}", err),
+        Poll1::Pending => panic!("read was pending"),
+    }
+}
+
+#[test]
+fn tokio_io_round_trip() {
+    let mut io = Cursor::new(DATA).read_into_tokio_1_compat().read_into_tokio_01_compat();
+
+    // tokio-io readers are used from within a v0.1 task.
+    let read = future::lazy(move || {
+        let mut read = Vec::new();
+        io.read_to_end(&mut read).map(|_| read)
+    }).wait().unwrap();
+
+    assert_eq!(read, DATA);
+}
+
+#[test]
+fn io_02_round_trip() {
+    let mut io = Cursor::new(DATA).io_02_read_into_tokio_1_compat(common::NoSpawn).read_into_io_02_compat();
+    let mut read = Vec::new();
+    let mut buf = [0; 5];
+    common::with_context(&common::noop_waker(), |cx| loop {
+        match io.poll_read(cx, &mut buf).unwrap() {
+            Async02::Ready(0) => break,
+            Async02::Ready(n) => read.extend_from_slice(&buf[..n]),
+            Async02::Pending => panic!("read was pending"),
+        }
+    });
+
+    assert_eq!(read, DATA);
+}
+
+#[test]
+fn tokio_io_reader_is_given_zeroed_buffer() {
+    let seen = Rc::new(Cell::new(None));
+    let mut io = Inspect(seen.clone()).read_into_tokio_1_compat();
+
+    assert_eq!(read_once(&mut io), (b"x".to_vec(), 4));
+    assert_eq!(seen.get(), Some(0));
+}
+
+#[test]
+fn io_02_reader_is_given_zeroed_buffer() {
+    let seen = Rc::new(Cell::new(None));
+    let mut io = Inspect(seen.clone()).io_02_read_into_tokio_1_compat(common::NoSpawn);
+
+    assert_eq!(read_once(&mut io), (b"x".to_vec(), 4));
+    assert_eq!(seen.get(), Some(0));
+}